@@ -1,15 +1,16 @@
 use std::{
     borrow::{Borrow, Cow},
     fmt,
-    hash::Hash,
-    ops::{Deref, DerefMut, Index, RangeBounds},
+    hash::{Hash, Hasher},
+    ops::{Bound, Deref, DerefMut, Index, Range, RangeBounds},
 };
 
+use rayon::prelude::*;
 use serde::*;
 use smallvec::SmallVec;
 
 /// Uiua's array shape type
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct Shape {
     dims: SmallVec<[usize; INLINE_DIMS]>,
@@ -201,148 +202,4039 @@ impl Shape {
         }
         Some(flat)
     }
+    /// Check that an array with this shape can be reversed
+    ///
+    /// Reversing a scalar is meaningless, so it is an error
+    pub fn validate_reversible(&self) -> Result<(), Cow<'static, str>> {
+        if self.is_empty() {
+            Err("Cannot reverse a scalar".into())
+        } else {
+            Ok(())
+        }
+    }
+    /// Get the bounding shape of a ragged structure
+    ///
+    /// `lengths` contains the lengths found at each depth.
+    /// Each axis is the maximum length at its depth.
+    pub fn bounding(lengths: &[Vec<usize>]) -> Shape {
+        (lengths.iter())
+            .map(|lens| lens.iter().copied().max().unwrap_or(0))
+            .collect()
+    }
+    /// Get the row-major strides of each axis
+    pub fn strides(&self) -> Vec<usize> {
+        let mut strides = vec![1; self.len()];
+        for i in (0..self.len().saturating_sub(1)).rev() {
+            strides[i] = strides[i + 1] * self.dims[i + 1];
+        }
+        strides
+    }
+    /// Get the column-major strides of each axis
+    ///
+    /// The first axis has a stride of 1
+    pub fn col_major_strides(&self) -> Vec<usize> {
+        let mut strides = Vec::with_capacity(self.len());
+        let mut stride = 1;
+        for &dim in &self.dims {
+            strides.push(stride);
+            stride *= dim;
+        }
+        strides
+    }
+    /// Split the leading axis `n` into `[n / d, d]`
+    pub fn factor_leading(&mut self, d: usize) -> Result<(), Cow<'static, str>> {
+        let Some(&n) = self.dims.first() else {
+            return Err("Cannot factor the leading axis of a scalar".into());
+        };
+        if d == 0 || n % d != 0 {
+            return Err(Cow::Owned(format!(
+                "Cannot factor leading axis of length {n} by {d}"
+            )));
+        }
+        self.dims[0] = n / d;
+        self.insert(1, d);
+        Ok(())
+    }
+    /// Merge all axes from `depth` onward into a single axis
+    ///
+    /// Does nothing if `depth` is at least the rank
+    pub fn flatten_to_depth(&mut self, depth: usize) {
+        if depth >= self.len() {
+            return;
+        }
+        let merged = self.dims[depth..].iter().product();
+        self.truncate(depth);
+        self.push(merged);
+    }
+    /// Get the shape of the matrix product of `[m, k]` and `[k, n]` shapes
+    pub fn matmul_shape(&self, other: &Shape) -> Result<Shape, Cow<'static, str>> {
+        let (&[m, k], &[k2, n]) = (&**self, &**other) else {
+            return Err(Cow::Owned(format!(
+                "Cannot multiply matrices with shapes {self} and {other}: \
+                both must be rank 2"
+            )));
+        };
+        if k != k2 {
+            return Err(Cow::Owned(format!(
+                "Cannot multiply matrices with shapes {self} and {other}: \
+                inner dimensions {k} and {k2} do not match"
+            )));
+        }
+        Ok([m, n].into())
+    }
+    /// Get the shape of a batched matrix product
+    ///
+    /// The last two axes of each shape are multiplied as matrices.
+    /// The leading batch axes are broadcast, aligned at their trailing axes.
+    pub fn batched_matmul_shape(&self, other: &Shape) -> Result<Shape, Cow<'static, str>> {
+        if self.len() < 2 || other.len() < 2 {
+            return Err(Cow::Owned(format!(
+                "Cannot multiply matrices with shapes {self} and {other}: \
+                both must be at least rank 2"
+            )));
+        }
+        let (a_batch, a_mat) = self.split_at(self.len() - 2);
+        let (b_batch, b_mat) = other.split_at(other.len() - 2);
+        let mat = Shape::from(a_mat)
+            .matmul_shape(&b_mat.into())
+            .map_err(|_| {
+                format!(
+                    "Cannot multiply matrices with shapes {self} and {other}: \
+                    inner dimensions {} and {} do not match",
+                    a_mat[1], b_mat[0]
+                )
+            })?;
+        let rank = a_batch.len().max(b_batch.len());
+        let mut shape = Shape::with_capacity(rank + 2);
+        for i in 0..rank {
+            let a = (a_batch.len() + i)
+                .checked_sub(rank)
+                .map_or(1, |j| a_batch[j]);
+            let b = (b_batch.len() + i)
+                .checked_sub(rank)
+                .map_or(1, |j| b_batch[j]);
+            shape.push(match (a, b) {
+                (a, b) if a == b => a,
+                (1, b) => b,
+                (a, 1) => a,
+                (a, b) => {
+                    return Err(Cow::Owned(format!(
+                        "Cannot multiply matrices with shapes {self} and {other}: \
+                        batch dimensions {a} and {b} do not match"
+                    )));
+                }
+            });
+        }
+        shape.extend_from_slice(&mat);
+        Ok(shape)
+    }
+    /// Get the number of elements, or `None` if it overflows
+    pub fn elements_checked(&self) -> Option<usize> {
+        if self.contains(&0) {
+            return Some(0);
+        }
+        self.iter()
+            .try_fold(1usize, |acc, &dim| acc.checked_mul(dim))
+    }
+    /// Extend the shape with the given dimensions if the element count does not overflow
+    ///
+    /// The shape is unchanged on error
+    pub fn try_extend_from_slice(&mut self, dims: &[usize]) -> Result<(), Cow<'static, str>> {
+        let mut extended = self.clone();
+        extended.extend_from_slice(dims);
+        if extended.product_overflows() {
+            return Err(Cow::Owned(format!(
+                "Extending shape {self} with {} would overflow the element count",
+                Shape::from(dims)
+            )));
+        }
+        self.extend_from_slice(dims);
+        Ok(())
+    }
+    /// Iterate over pairs of consecutive dimensions
+    pub fn dim_pairs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.dims.windows(2).map(|w| (w[0], w[1]))
+    }
+    /// Check if no dimension is smaller than the one before it
+    pub fn is_non_decreasing(&self) -> bool {
+        self.dim_pairs().all(|(a, b)| a <= b)
+    }
+    /// Check if no dimension is larger than the one before it
+    pub fn is_non_increasing(&self) -> bool {
+        self.dim_pairs().all(|(a, b)| a >= b)
+    }
+    fn check_axis(&self, axis: usize) -> Result<(), Cow<'static, str>> {
+        if axis < self.len() {
+            Ok(())
+        } else {
+            Err(Cow::Owned(format!(
+                "Axis {axis} is out of bounds for shape {self}"
+            )))
+        }
+    }
+    /// Get a copy of the shape with the given axis set to `dim`
+    pub fn with_axis(&self, axis: usize, dim: usize) -> Result<Shape, Cow<'static, str>> {
+        self.check_axis(axis)?;
+        let mut shape = self.clone();
+        shape[axis] = dim;
+        Ok(shape)
+    }
+    /// Get a compact string for use in keys
+    ///
+    /// Dimensions are joined by `.`, and a scalar is the empty string.
+    /// This format is stable.
+    pub fn key_string(&self) -> String {
+        let mut s = String::new();
+        for (i, dim) in self.dims.iter().enumerate() {
+            if i > 0 {
+                s.push('.');
+            }
+            s.push_str(&dim.to_string());
+        }
+        s
+    }
+    /// Parse a shape from JSON
+    ///
+    /// Accepts either an array of dimensions or an object with a `dims` array
+    pub fn from_json(value: &serde_json::Value) -> Result<Shape, Cow<'static, str>> {
+        use serde_json::Value;
+        let dims = match value {
+            Value::Array(dims) => dims,
+            Value::Object(obj) => match obj.get("dims") {
+                Some(Value::Array(dims)) => dims,
+                Some(_) => return Err("Shape `dims` must be an array".into()),
+                None => return Err("Shape object is missing `dims`".into()),
+            },
+            _ => return Err("Shape must be an array or an object with `dims`".into()),
+        };
+        (dims.iter())
+            .map(|dim| {
+                (dim.as_u64())
+                    .and_then(|d| usize::try_from(d).ok())
+                    .ok_or_else(|| Cow::Owned(format!("Invalid shape dimension {dim}")))
+            })
+            .collect()
+    }
+    /// Get the sum of the dimensions, saturating on overflow
+    pub fn dims_sum(&self) -> usize {
+        self.iter().fold(0, |acc, &dim| acc.saturating_add(dim))
+    }
+    /// Get the largest dimension
+    pub fn dims_max(&self) -> Option<usize> {
+        self.iter().copied().max()
+    }
+    /// Get the smallest dimension
+    pub fn dims_min(&self) -> Option<usize> {
+        self.iter().copied().min()
+    }
+    /// Split the last axis `n` into `[n / factor, factor]`
+    pub fn split_last_axis(&mut self, factor: usize) -> Result<(), Cow<'static, str>> {
+        let Some(&n) = self.dims.last() else {
+            return Err("Cannot split the last axis of a scalar".into());
+        };
+        if factor == 0 || n % factor != 0 {
+            return Err(Cow::Owned(format!(
+                "Cannot split last axis of length {n} by {factor}"
+            )));
+        }
+        *self.dims.last_mut().unwrap() = n / factor;
+        self.push(factor);
+        Ok(())
+    }
+    /// Multiply the last two axes into one
+    pub fn merge_last_two(&mut self) -> Result<(), Cow<'static, str>> {
+        if self.len() < 2 {
+            return Err(Cow::Owned(format!(
+                "Cannot merge the last two axes of shape {self}"
+            )));
+        }
+        let last = self.pop().unwrap();
+        *self.dims.last_mut().unwrap() *= last;
+        Ok(())
+    }
+    /// Get the inclusive running products of the dimensions
+    ///
+    /// The exclusive running products are the [`Shape::col_major_strides`]
+    pub fn cumulative_products(&self) -> Vec<usize> {
+        (self.iter())
+            .scan(1, |acc, &dim| {
+                *acc *= dim;
+                Some(*acc)
+            })
+            .collect()
+    }
+    /// Remove up to `n` trailing axes
+    pub fn trim_trailing(&mut self, n: usize) {
+        self.truncate(self.len().saturating_sub(n));
+    }
+    /// Remove up to `n` leading axes
+    pub fn trim_leading(&mut self, n: usize) {
+        self.drain(..n.min(self.len()));
+    }
+    /// Convert `u64` dimensions to a shape
+    pub fn try_from_u64(dims: &[u64]) -> Result<Shape, Cow<'static, str>> {
+        Shape::try_from_dims(dims)
+    }
+    fn try_from_dims<T>(dims: &[T]) -> Result<Shape, Cow<'static, str>>
+    where
+        T: Copy + fmt::Display,
+        usize: TryFrom<T>,
+    {
+        (dims.iter())
+            .map(|&dim| {
+                usize::try_from(dim).map_err(|_| {
+                    Cow::Owned(format!("Dimension {dim} is too large for this platform"))
+                })
+            })
+            .collect()
+    }
+    /// Replace all but the last axis with `new_outer`
+    pub fn reshape_keeping_last(&self, new_outer: &[usize]) -> Result<Shape, Cow<'static, str>> {
+        let Some(&last) = self.dims.last() else {
+            return Err("Cannot reshape a scalar while keeping its last axis".into());
+        };
+        let mut shape = Shape::from(new_outer);
+        shape.push(last);
+        if shape.elements_checked() != Some(self.elements()) {
+            return Err(Cow::Owned(format!(
+                "Cannot reshape {self} to {shape} because the element counts differ"
+            )));
+        }
+        Ok(shape)
+    }
+    /// Get the shape of a "valid" convolution with the given kernel
+    ///
+    /// Each axis becomes `dim - kernel_dim + 1`
+    pub fn valid_conv_shape(&self, kernel: &[usize]) -> Result<Shape, Cow<'static, str>> {
+        if kernel.len() != self.len() {
+            return Err(Cow::Owned(format!(
+                "Kernel {} does not match the rank of shape {self}",
+                Shape::from(kernel)
+            )));
+        }
+        (self.iter().zip(kernel))
+            .map(|(&dim, &k)| {
+                if k == 0 || k > dim {
+                    Err(Cow::Owned(format!(
+                        "Kernel {} does not fit in shape {self}",
+                        Shape::from(kernel)
+                    )))
+                } else {
+                    Ok(dim - k + 1)
+                }
+            })
+            .collect()
+    }
+    /// Get the shape of a strided convolution with the given kernel
+    ///
+    /// Each axis becomes `(dim - kernel_dim) / stride + 1`
+    pub fn conv_output_shape(
+        &self,
+        kernel: &[usize],
+        stride: &[usize],
+    ) -> Result<Shape, Cow<'static, str>> {
+        if stride.len() != self.len() {
+            return Err(Cow::Owned(format!(
+                "Stride {} does not match the rank of shape {self}",
+                Shape::from(stride)
+            )));
+        }
+        if stride.contains(&0) {
+            return Err("Convolution strides must be nonzero".into());
+        }
+        let mut shape = self.valid_conv_shape(kernel)?;
+        for (dim, &s) in shape.iter_mut().zip(stride) {
+            *dim = (*dim - 1) / s + 1;
+        }
+        Ok(shape)
+    }
+    /// Get a terse representation of the shape for logging
+    ///
+    /// Dimensions are joined by `x` with no brackets
+    pub fn compact(&self) -> impl fmt::Debug + '_ {
+        struct Compact<'a>(&'a Shape);
+        impl fmt::Debug for Compact<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                for (i, dim) in self.0.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "x")?;
+                    }
+                    write!(f, "{dim}")?;
+                }
+                Ok(())
+            }
+        }
+        Compact(self)
+    }
+    /// Check that the last axis of an index shape is at most `max`
+    ///
+    /// Returns the last axis length
+    pub fn validate_index_last_axis(&self, max: usize) -> Result<usize, Cow<'static, str>> {
+        match self.dims.last() {
+            Some(&len) if len <= max => Ok(len),
+            Some(&len) => Err(Cow::Owned(format!(
+                "Index shape {self} has last axis length {len}, \
+                but the maximum is {max}"
+            ))),
+            None => Err("Index array cannot be a scalar".into()),
+        }
+    }
+    /// Check if two shapes have the same number of elements
+    ///
+    /// Returns `false` if either element count overflows
+    pub fn same_element_count(&self, other: &Shape) -> bool {
+        match (self.elements_checked(), other.elements_checked()) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+    /// Check that two shapes have the same number of elements
+    pub fn assert_same_element_count(&self, other: &Shape) -> Result<(), Cow<'static, str>> {
+        if self.same_element_count(other) {
+            return Ok(());
+        }
+        let count = |shape: &Shape| {
+            (shape.elements_checked()).map_or_else(|| "too many".into(), |n| n.to_string())
+        };
+        Err(Cow::Owned(format!(
+            "Shapes {self} and {other} have different element counts ({} and {})",
+            count(self),
+            count(other)
+        )))
+    }
+    /// Get the dimensions in the given range as a new shape
+    ///
+    /// Unlike [`Shape::subshape`], the range is clamped to the rank
+    pub fn sub_shape(&self, range: impl RangeBounds<usize>) -> Shape {
+        let start = match range.start_bound() {
+            Bound::Included(&i) => i,
+            Bound::Excluded(&i) => i.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&i) => i.saturating_add(1),
+            Bound::Excluded(&i) => i,
+            Bound::Unbounded => self.len(),
+        };
+        let end = end.min(self.len());
+        Shape::from(&self.dims[start.min(end)..end])
+    }
+    /// Check that custom strides are valid for a view with this shape
+    ///
+    /// Any axis longer than 1 must have a nonzero stride
+    pub fn validate_strides(&self, strides: &[usize]) -> Result<(), Cow<'static, str>> {
+        if strides.len() != self.len() {
+            return Err(Cow::Owned(format!(
+                "Shape {self} has rank {} but {} strides were given",
+                self.len(),
+                strides.len()
+            )));
+        }
+        for (axis, (&dim, &stride)) in self.iter().zip(strides).enumerate() {
+            if dim > 1 && stride == 0 {
+                return Err(Cow::Owned(format!(
+                    "Axis {axis} of shape {self} has length {dim} but a stride of 0"
+                )));
+            }
+        }
+        Ok(())
+    }
+    /// Get the shape with leading unit axes removed, for display
+    ///
+    /// Leading 1s are removed until a non-1 axis is reached or only one axis remains.
+    /// A scalar stays a scalar.
+    pub fn normalize_display(&self) -> Shape {
+        let leading = self.iter().take_while(|&&dim| dim == 1).count();
+        let leading = leading.min(self.len().saturating_sub(1));
+        Shape::from(&self.dims[leading..])
+    }
+    /// Check if the number of elements overflows
+    pub fn product_overflows(&self) -> bool {
+        self.elements_checked().is_none()
+    }
+    /// Get a rank-2 grid shape for displaying the elements within a width
+    ///
+    /// The grid has `min(elements, max_cols)` columns, but at least 1.
+    /// An empty shape gives `[0 × 0]`.
+    pub fn grid_for_width(&self, max_cols: usize) -> Shape {
+        let n = self.elements();
+        if n == 0 {
+            return [0, 0].into();
+        }
+        let cols = n.min(max_cols).max(1);
+        [n.div_ceil(cols), cols].into()
+    }
+    /// Get the row-major stride of a single axis
+    pub fn axis_stride(&self, axis: usize) -> Option<usize> {
+        (axis < self.len()).then(|| self.dims[axis + 1..].iter().product())
+    }
+    /// Get the shape that results from broadcasting two shapes together
+    ///
+    /// Shapes are aligned at their trailing axes. Missing and unit axes are stretched.
+    /// Returns `None` if the shapes are incompatible.
+    pub fn broadcast_with(&self, other: &Shape) -> Option<Shape> {
+        let rank = self.len().max(other.len());
+        let mut shape = Shape::with_capacity(rank);
+        for i in 0..rank {
+            let a = (self.len() + i).checked_sub(rank).map_or(1, |j| self[j]);
+            let b = (other.len() + i).checked_sub(rank).map_or(1, |j| other[j]);
+            shape.push(match (a, b) {
+                (a, b) if a == b => a,
+                (1, b) => b,
+                (a, 1) => a,
+                _ => return None,
+            });
+        }
+        Some(shape)
+    }
+    /// Combine two shapes, preferring non-unit dimensions
+    ///
+    /// This is the same as [`Shape::broadcast_with`]. It is meant for merging
+    /// shape metadata from two sources, where the more specific shape should win.
+    pub fn more_specific(&self, other: &Shape) -> Option<Shape> {
+        self.broadcast_with(other)
+    }
+    /// Iterate over the positions along an axis and the shape of the cell at each
+    ///
+    /// Each cell has this shape with `axis` removed
+    pub fn cells_along(
+        &self,
+        axis: usize,
+    ) -> Result<impl Iterator<Item = (usize, Shape)> + '_, Cow<'static, str>> {
+        self.check_axis(axis)?;
+        let mut cell = self.clone();
+        cell.remove(axis);
+        Ok((0..self[axis]).map(move |i| (i, cell.clone())))
+    }
+    /// Count the cells whose coordinate sums are even and odd
+    pub fn parity_counts(&self) -> (usize, usize) {
+        let n = self.elements();
+        if self.iter().any(|&dim| dim % 2 == 0) {
+            (n / 2, n / 2)
+        } else {
+            // All axes are odd, so there is one more even cell than odd
+            (n.div_ceil(2), n / 2)
+        }
+    }
+    /// Get the shape with its axes permuted
+    ///
+    /// Axis `i` of the result is axis `perm[i]` of this shape
+    pub fn permute_axes(&self, perm: &[usize]) -> Result<Shape, Cow<'static, str>> {
+        let mut seen = vec![false; self.len()];
+        let is_perm = perm.len() == self.len()
+            && perm
+                .iter()
+                .all(|&p| p < seen.len() && !std::mem::replace(&mut seen[p], true));
+        if !is_perm {
+            return Err(Cow::Owned(format!(
+                "{perm:?} is not a valid axis permutation for shape {self}"
+            )));
+        }
+        Ok(perm.iter().map(|&p| self[p]).collect())
+    }
+    /// Get the shape after permuting axes and then reshaping to `target`
+    pub fn transpose_then_reshape(
+        &self,
+        perm: &[usize],
+        target: &Shape,
+    ) -> Result<Shape, Cow<'static, str>> {
+        self.permute_axes(perm)?.assert_same_element_count(target)?;
+        Ok(target.clone())
+    }
+    /// Get the index of the largest axis
+    ///
+    /// Ties go to the first axis
+    pub fn largest_axis(&self) -> Option<usize> {
+        (self.iter().enumerate().rev())
+            .max_by_key(|&(_, &dim)| dim)
+            .map(|(i, _)| i)
+    }
+    /// Get the index of the smallest axis
+    ///
+    /// Ties go to the first axis
+    pub fn smallest_axis(&self) -> Option<usize> {
+        (self.iter().enumerate())
+            .min_by_key(|&(_, &dim)| dim)
+            .map(|(i, _)| i)
+    }
+    /// Remove dimensions in the given range and return them
+    pub fn drain_collect(&mut self, range: impl RangeBounds<usize>) -> Vec<usize> {
+        self.dims.drain(range).collect()
+    }
+    /// Move the axis at `from` so that it ends up at `to`
+    pub fn move_axis(&mut self, from: usize, to: usize) -> Result<(), Cow<'static, str>> {
+        self.check_axis(from)?;
+        self.check_axis(to)?;
+        let dim = self.remove(from);
+        self.insert(to, dim);
+        Ok(())
+    }
+    /// Check if all axes have the same length
+    pub fn all_axes_equal(&self) -> bool {
+        self.dim_pairs().all(|(a, b)| a == b)
+    }
+    /// Convert the shape into a `Vec` of its dimensions
+    ///
+    /// This only allocates if the dimensions are stored inline
+    pub fn into_dims_vec(self) -> Vec<usize> {
+        self.dims.into_vec()
+    }
+    /// Get the shape of a dense array built from rows of the given lengths
+    ///
+    /// Shorter rows are padded to the longest. No rows gives `[0 × 0]`.
+    pub fn from_row_lengths(lengths: &[usize]) -> Shape {
+        [lengths.len(), lengths.iter().copied().max().unwrap_or(0)].into()
+    }
+    /// Get the number of elements after adding `extra_rows` rows
+    pub fn elements_plus(&self, extra_rows: usize) -> Option<usize> {
+        let rows = self.row_count().checked_add(extra_rows)?;
+        Shape::from(self.row_slice())
+            .elements_checked()?
+            .checked_mul(rows)
+    }
+    /// Iterate over `(axis, dim)` pairs from the last axis to the first
+    pub fn axes_rev_enumerated(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.iter().copied().enumerate().rev()
+    }
+    /// Check that this shape equals `expected`, describing the differences if not
+    pub fn assert_eq_shape(&self, expected: &Shape) -> Result<(), String> {
+        if self == expected {
+            return Ok(());
+        }
+        let mut message = format!("Expected shape {expected}, found {self}");
+        if self.len() != expected.len() {
+            message.push_str(&format!(
+                "\n  rank: expected {}, found {}",
+                expected.len(),
+                self.len()
+            ));
+        }
+        for (axis, (&found, &exp)) in self.iter().zip(expected.iter()).enumerate() {
+            if found != exp {
+                message.push_str(&format!("\n  axis {axis}: expected {exp}, found {found}"));
+            }
+        }
+        Err(message)
+    }
+    /// Get a copy of the shape with a leading dimension added
+    pub fn with_prepended(&self, dim: usize) -> Shape {
+        let mut shape = Shape::with_capacity(self.len() + 1);
+        shape.push(dim);
+        shape.extend_from_slice(self);
+        shape
+    }
+    /// Iterate over the dimensions by value
+    ///
+    /// ```rust
+    /// use uiua::Shape;
+    ///
+    /// let shape = Shape::from([2, 3, 4]);
+    /// assert_eq!(shape.iter_copied().sum::<usize>(), 9);
+    /// ```
+    pub fn iter_copied(&self) -> impl Iterator<Item = usize> + '_ {
+        self.iter().copied()
+    }
+    /// Get `(dim, stride)` pairs for each axis, from outermost to innermost
+    ///
+    /// These are the bounds and offset steps for a nested loop over the elements.
+    pub fn loop_bounds(&self) -> Vec<(usize, usize)> {
+        self.iter().copied().zip(self.strides()).collect()
+    }
+    /// Keep the first `keep` axes and merge the rest into one
+    ///
+    /// This is the same as [`Shape::flatten_to_depth`]
+    pub fn flatten_after(&mut self, keep: usize) {
+        self.flatten_to_depth(keep);
+    }
+    /// Validate a reshape to `target` that will read elements in the given order
+    ///
+    /// The order does not affect the resulting shape, only how a later reshape
+    /// of the data reads its elements.
+    pub fn reshape_with_order(
+        &self,
+        target: &Shape,
+        order: MajorOrder,
+    ) -> Result<Shape, Cow<'static, str>> {
+        self.assert_same_element_count(target).map_err(|e| {
+            let order = match order {
+                MajorOrder::Row => "row",
+                MajorOrder::Column => "column",
+            };
+            Cow::Owned(format!("Cannot {order}-major reshape: {e}"))
+        })?;
+        Ok(target.clone())
+    }
+    /// Prepend `fill`-length axes until the shape has the given rank
+    ///
+    /// The shape is unchanged if the element count would overflow
+    pub fn pad_to_rank_with(&mut self, rank: usize, fill: usize) -> Result<(), Cow<'static, str>> {
+        let extra = rank.saturating_sub(self.len());
+        let mut padded = Shape::with_capacity(extra + self.len());
+        padded.extend(std::iter::repeat_n(fill, extra));
+        padded.extend_from_slice(self);
+        if padded.product_overflows() {
+            return Err(Cow::Owned(format!(
+                "Padding shape {self} to rank {rank} with {fill}s \
+                would overflow the element count"
+            )));
+        }
+        *self = padded;
+        Ok(())
+    }
+    /// Multiply `acc` by the number of elements, or `None` if it overflows
+    ///
+    /// This can be chained to get the total element count of several shapes
+    pub fn accumulate_elements(&self, acc: usize) -> Option<usize> {
+        acc.checked_mul(self.elements_checked()?)
+    }
+    /// Check if the shape is rank 1
+    pub fn is_list(&self) -> bool {
+        self.len() == 1
+    }
+    /// Check if the shape is `[1 × n]`
+    pub fn is_row_vector(&self) -> bool {
+        matches!(**self, [1, _])
+    }
+    /// Check if the shape is `[n × 1]`
+    pub fn is_column_vector(&self) -> bool {
+        matches!(**self, [_, 1])
+    }
+    /// Stretch a unit axis to the given length
+    pub fn expand_axis(&mut self, axis: usize, len: usize) -> Result<(), Cow<'static, str>> {
+        self.check_axis(axis)?;
+        if self[axis] != 1 {
+            return Err(Cow::Owned(format!(
+                "Cannot expand axis {axis} of shape {self} because its length is not 1"
+            )));
+        }
+        self[axis] = len;
+        Ok(())
+    }
+    /// Split each axis longer than `threshold` into tiles of `threshold`
+    ///
+    /// An axis of length `n` becomes `[ceil(n / threshold), threshold]`.
+    /// If `n` is not divisible by `threshold`, the last tile is padded,
+    /// so the element count may grow.
+    /// A threshold of 0 leaves the shape unchanged.
+    pub fn split_large_axes(&self, threshold: usize) -> Shape {
+        let mut shape = Shape::with_capacity(self.len());
+        for &dim in self {
+            if threshold > 0 && dim > threshold {
+                shape.push(dim.div_ceil(threshold));
+                shape.push(threshold);
+            } else {
+                shape.push(dim);
+            }
+        }
+        shape
+    }
+    /// Broadcast several shapes together
+    ///
+    /// On failure, the error names the two conflicting shapes by index
+    /// and the conflicting axis, counted from the end
+    pub fn broadcast_all(shapes: &[Shape]) -> Result<Shape, Cow<'static, str>> {
+        let rank = shapes.iter().map(|s| s.len()).max().unwrap_or(0);
+        // Each axis's length and the index of the shape it came from
+        let mut axes: Vec<(usize, usize)> = vec![(1, 0); rank];
+        for (i, shape) in shapes.iter().enumerate() {
+            let pairs = shape.iter().rev().zip(axes.iter_mut().rev());
+            for (k, (&dim, axis)) in pairs.enumerate() {
+                match *axis {
+                    (1, _) => *axis = (dim, i),
+                    (len, _) if len == dim || dim == 1 => {}
+                    (len, j) => {
+                        return Err(Cow::Owned(format!(
+                            "Cannot broadcast shape {j} {} with shape {i} {shape}: \
+                            axis -{} has lengths {len} and {dim}",
+                            shapes[j],
+                            k + 1
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(axes.into_iter().map(|(dim, _)| dim).collect())
+    }
+    /// Check if the axis has length 1, or `None` if it is out of bounds
+    pub fn axis_is_unit(&self, axis: usize) -> Option<bool> {
+        self.get(axis).map(|&dim| dim == 1)
+    }
+    /// Swap the first two axes
+    pub fn swap_first_two(&mut self) -> Result<(), Cow<'static, str>> {
+        if self.len() < 2 {
+            return Err(Cow::Owned(format!(
+                "Cannot swap the first two axes of shape {self}"
+            )));
+        }
+        self.swap(0, 1);
+        Ok(())
+    }
+    /// Get the index of the element at the given flat offset as a fixed-size array
+    ///
+    /// Returns `None` if the rank is not `N` or the offset is out of bounds
+    pub fn flat_to_dims_n<const N: usize>(&self, flat: usize) -> Option<[usize; N]> {
+        if self.len() != N || flat >= self.elements() {
+            return None;
+        }
+        let mut index = [0; N];
+        let mut flat = flat;
+        for (i, &dim) in index.iter_mut().zip(&self.dims).rev() {
+            *i = flat % dim;
+            flat /= dim;
+        }
+        Some(index)
+    }
+    /// Get the flat offset of a fixed-size index
+    ///
+    /// Returns `None` if the rank is not `N` or the index is out of bounds
+    pub fn dims_to_flat_n<const N: usize>(&self, index: &[usize; N]) -> Option<usize> {
+        if self.len() != N {
+            return None;
+        }
+        self.dims_to_flat(index)
+    }
+    /// Set the shape to `target` if they have the same number of elements
+    ///
+    /// The existing allocation is reused where possible
+    pub fn reshape_in_place(&mut self, target: &Shape) -> Result<(), Cow<'static, str>> {
+        self.assert_same_element_count(target)?;
+        self.dims.clear();
+        self.dims.extend_from_slice(target);
+        Ok(())
+    }
+    /// Iterate over the flat offsets of the elements in parallel
+    pub fn par_offsets(&self) -> impl ParallelIterator<Item = usize> {
+        (0..self.elements()).into_par_iter()
+    }
+    /// Iterate in parallel over the element ranges of groups of `chunk_rows` rows
+    ///
+    /// A `chunk_rows` of 0 is treated as 1
+    pub fn par_row_spans(&self, chunk_rows: usize) -> impl ParallelIterator<Item = Range<usize>> {
+        let rows = self.row_count();
+        let row_len = self.row_len();
+        let chunk_rows = chunk_rows.max(1);
+        (0..rows.div_ceil(chunk_rows))
+            .into_par_iter()
+            .map(move |i| {
+                let start = i * chunk_rows;
+                let end = (start + chunk_rows).min(rows);
+                start * row_len..end * row_len
+            })
+    }
+    /// Estimate the cost of materializing a transpose
+    ///
+    /// This is the number of elements, saturating on overflow
+    pub fn transpose_cost(&self) -> usize {
+        self.iter()
+            .fold(1, |acc: usize, &dim| acc.saturating_mul(dim))
+    }
+    /// Check if a transpose should be a lazy view rather than a copy
+    pub fn prefer_lazy_transpose(&self, threshold: usize) -> bool {
+        self.transpose_cost() > threshold
+    }
+    /// Parse a shape spec like `"auto x 4"`
+    ///
+    /// Dimensions are separated by `x`. `auto` or `_` marks a dimension to be inferred.
+    /// An empty spec is a scalar.
+    pub fn parse_spec(s: &str) -> Result<Vec<Option<usize>>, Cow<'static, str>> {
+        if s.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        (s.split('x').map(str::trim))
+            .map(|token| match token {
+                "auto" | "_" => Ok(None),
+                _ => (token.parse().map(Some))
+                    .map_err(|_| Cow::Owned(format!("Invalid shape spec dimension `{token}`"))),
+            })
+            .collect()
+    }
+    /// Get the flat offset of a signed index
+    ///
+    /// Returns `None` if the index's rank doesn't match
+    /// or any coordinate is negative or out of bounds
+    pub fn signed_offset(&self, index: &[isize]) -> Option<usize> {
+        if index.len() != self.len() {
+            return None;
+        }
+        self.i_dims_to_flat(index)
+    }
+    /// Get the flat offset of a signed index, wrapping negative coordinates
+    ///
+    /// Each coordinate wraps around its axis, so negative coordinates count from the end.
+    /// Returns `None` if the index's rank doesn't match or any axis has length 0.
+    pub fn signed_offset_wrapping(&self, index: &[isize]) -> Option<usize> {
+        if index.len() != self.len() {
+            return None;
+        }
+        let mut flat = 0;
+        for (&dim, &i) in self.dims.iter().zip(index) {
+            if dim == 0 {
+                return None;
+            }
+            flat = flat * dim + i.rem_euclid(dim as isize) as usize;
+        }
+        Some(flat)
+    }
+    /// Get the index that the given index maps to when `axis` is reversed
+    ///
+    /// Returns `None` if the axis or index is out of bounds
+    pub fn reflect_index(&self, axis: usize, index: &[usize]) -> Option<Vec<usize>> {
+        if axis >= self.len()
+            || index.len() != self.len()
+            || index.iter().zip(&self.dims).any(|(i, d)| i >= d)
+        {
+            return None;
+        }
+        let mut reflected = index.to_vec();
+        reflected[axis] = self[axis] - 1 - index[axis];
+        Some(reflected)
+    }
+    /// Add the dimensions of two shapes of the same rank
+    pub fn add_dims(&self, other: &Shape) -> Result<Shape, Cow<'static, str>> {
+        if self.len() != other.len() {
+            return Err(Cow::Owned(format!(
+                "Cannot add dimensions of shapes {self} and {other} \
+                because their ranks differ"
+            )));
+        }
+        (self.iter().zip(other).enumerate())
+            .map(|(axis, (&a, &b))| {
+                a.checked_add(b).ok_or_else(|| {
+                    Cow::Owned(format!(
+                        "Adding shapes {self} and {other} overflows at axis {axis}"
+                    ))
+                })
+            })
+            .collect()
+    }
+    /// Check if every dimension is a power of two
+    pub fn all_dims_power_of_two(&self) -> bool {
+        self.iter().all(|dim| dim.is_power_of_two())
+    }
+    /// Round each dimension up to the next power of two
+    ///
+    /// Returns `None` if a dimension or the element count would overflow
+    pub fn next_power_of_two_shape(&self) -> Option<Shape> {
+        let shape: Shape = (self.iter())
+            .map(|dim| dim.checked_next_power_of_two())
+            .collect::<Option<_>>()?;
+        (!shape.product_overflows()).then_some(shape)
+    }
+    /// Get the shape padded to power-of-two dimensions and the padding for each axis
+    pub fn pad_axes_to_pow2(&self) -> Result<(Shape, Vec<usize>), Cow<'static, str>> {
+        let padded = self.next_power_of_two_shape().ok_or_else(|| {
+            Cow::Owned(format!(
+                "Padding shape {self} to powers of two would overflow"
+            ))
+        })?;
+        let pads = padded
+            .iter()
+            .zip(self)
+            .map(|(new, old)| new - old)
+            .collect();
+        Ok((padded, pads))
+    }
+    /// Iterate over the coordinates along an axis
+    pub fn axis_coords(
+        &self,
+        axis: usize,
+    ) -> Result<impl Iterator<Item = usize>, Cow<'static, str>> {
+        self.check_axis(axis)?;
+        Ok(0..self[axis])
+    }
+    /// Split the shape at the given index, or return `None` if it is past the end
+    pub fn checked_split_off(&mut self, at: usize) -> Option<Shape> {
+        (at <= self.len()).then(|| self.split_off(at))
+    }
+    /// Get the flat offset of an index without bounds checking
+    ///
+    /// # Safety
+    /// `index` must have the same length as the shape, and each coordinate
+    /// must be less than its dimension. Otherwise, the offset may be past the end
+    /// of the array's data, and using it to index without checks is undefined behavior.
+    pub unsafe fn dims_to_flat_unchecked(&self, index: &[usize]) -> usize {
+        let mut flat = 0;
+        for (&dim, &i) in self.dims.iter().zip(index) {
+            flat = flat * dim + i;
+        }
+        flat
+    }
+    /// Get structured information about the shape
+    pub fn info(&self) -> ShapeInfo {
+        ShapeInfo {
+            rank: self.len(),
+            dims: self.to_vec(),
+            elements: (self.iter()).fold(1, |acc: u128, &dim| acc.saturating_mul(dim as u128)),
+            is_empty: self.contains(&0),
+            is_scalar: self.is_empty(),
+        }
+    }
+    /// Get a rank-2 shape with about `target_elements` elements and the same aspect ratio
+    ///
+    /// The row count is `sqrt(target_elements * rows / cols)` rounded to the nearest integer
+    /// and clamped to `1..=target_elements`.
+    /// The column count is `target_elements / rows` rounded to the nearest integer, but at least 1.
+    /// Returns `None` if the shape is not rank 2, if it is empty,
+    /// or if `target_elements` is 0.
+    pub fn resize_keeping_aspect(&self, target_elements: usize) -> Option<Shape> {
+        let &[rows, cols] = &**self else {
+            return None;
+        };
+        if rows == 0 || cols == 0 || target_elements == 0 {
+            return None;
+        }
+        let target = target_elements as f64;
+        let new_rows = (target * rows as f64 / cols as f64).sqrt().round();
+        let new_rows = new_rows.clamp(1.0, target);
+        let new_cols = (target / new_rows).round().max(1.0);
+        Some([new_rows as usize, new_cols as usize].into())
+    }
+    /// Format the shape with its dimensions in the given base
+    pub fn display_radix(&self, radix: u32) -> Result<String, Cow<'static, str>> {
+        if !(2..=36).contains(&radix) {
+            return Err(Cow::Owned(format!(
+                "Radix must be between 2 and 36, but it is {radix}"
+            )));
+        }
+        let mut s = String::from("[");
+        for (i, &dim) in self.iter().enumerate() {
+            if i > 0 {
+                s.push_str(" × ");
+            }
+            let mut digits = Vec::new();
+            let mut n = dim;
+            loop {
+                digits.push(char::from_digit((n % radix as usize) as u32, radix).unwrap());
+                n /= radix as usize;
+                if n == 0 {
+                    break;
+                }
+            }
+            s.extend(digits.into_iter().rev());
+        }
+        s.push(']');
+        Ok(s)
+    }
+    /// Get the number of elements in the result of an operation
+    ///
+    /// Returns `None` if the count overflows or a reduced axis is out of bounds
+    pub fn cells_for(&self, op: ShapeOp) -> Option<usize> {
+        match op {
+            ShapeOp::Transpose | ShapeOp::Reverse => self.elements_checked(),
+            ShapeOp::Reshape(target) => target.elements_checked(),
+            ShapeOp::Reduce(axis) => {
+                if axis >= self.len() {
+                    return None;
+                }
+                let mut shape = self.clone();
+                shape.remove(axis);
+                shape.elements_checked()
+            }
+        }
+    }
+    /// Find the first axis other than `axis` where two shapes disagree
+    ///
+    /// Returns `(conflicting_axis, self_dim, other_dim)`.
+    /// Only axes that both shapes have are compared.
+    pub fn concat_conflict(&self, other: &Shape, axis: usize) -> Option<(usize, usize, usize)> {
+        (self.iter().zip(other).enumerate())
+            .find(|&(i, (a, b))| i != axis && a != b)
+            .map(|(i, (&a, &b))| (i, a, b))
+    }
+    /// Find the first trailing-aligned axis where two shapes cannot be broadcast
+    ///
+    /// Returns `(axis_from_end, self_dim, other_dim)`, where an `axis_from_end` of 1
+    /// is the last axis.
+    pub fn broadcast_conflict(&self, other: &Shape) -> Option<(usize, usize, usize)> {
+        (self.iter().rev().zip(other.iter().rev()).enumerate())
+            .find(|&(_, (&a, &b))| a != b && a != 1 && b != 1)
+            .map(|(i, (&a, &b))| (i + 1, a, b))
+    }
+    /// Get the total number of elements in several shapes
+    ///
+    /// This is the length of their deshaped concatenation.
+    /// Returns `None` on overflow.
+    pub fn total_cells_of(shapes: &[Shape]) -> Option<usize> {
+        (shapes.iter()).try_fold(0usize, |acc, shape| {
+            acc.checked_add(shape.elements_checked()?)
+        })
+    }
+    /// Check if this shape can be stretched to `target` by broadcasting
+    ///
+    /// Unlike [`Shape::broadcast_compatible`], only this shape may be stretched
+    pub fn is_broadcastable_to(&self, target: &Shape) -> bool {
+        self.len() <= target.len()
+            && (self.iter().rev().zip(target.iter().rev())).all(|(&a, &b)| a == b || a == 1)
+    }
+    /// Check if two shapes can be broadcast together
+    ///
+    /// This is the same as [`Shape::broadcast_with`] succeeding, but does not build the result
+    pub fn broadcast_compatible(&self, other: &Shape) -> bool {
+        (self.iter().rev().zip(other.iter().rev())).all(|(&a, &b)| a == b || a == 1 || b == 1)
+    }
+    /// Remove unit axes, but keep at least `min_rank` axes
+    ///
+    /// Unit axes are removed from first to last until the rank is `min_rank`.
+    /// Non-unit axes are never removed.
+    pub fn trim_unit_axes_to_rank(&self, min_rank: usize) -> Shape {
+        let mut removable = self.len().saturating_sub(min_rank);
+        (self.iter().copied())
+            .filter(|&dim| {
+                if dim == 1 && removable > 0 {
+                    removable -= 1;
+                    false
+                } else {
+                    true
+                }
+            })
+            .collect()
+    }
+    /// Split the last axis into channels and move the channel axis to the front
+    ///
+    /// An interleaved `[.., n]` shape becomes a planar `[channels, .., n / channels]`
+    pub fn deinterleave_last(&mut self, channels: usize) -> Result<(), Cow<'static, str>> {
+        self.split_last_axis(channels)?;
+        let last = self.len() - 1;
+        self.move_axis(last, 0)
+    }
+    /// Describe the effect of reshaping to `target`
+    pub fn reshape_preview(&self, target: &Shape) -> String {
+        let (from, to) = (self.elements(), target.elements());
+        let effect = match from.cmp(&to) {
+            std::cmp::Ordering::Equal => format!("same {from} elements"),
+            std::cmp::Ordering::Less => {
+                format!("element count changes {from} → {to}, fill needed")
+            }
+            std::cmp::Ordering::Greater => {
+                format!("element count changes {from} → {to}, elements truncated")
+            }
+        };
+        format!("reshape {self} → {target} ({effect})")
+    }
+    /// Guess names for the axes based on the rank
+    ///
+    /// Ranks other than 1, 2, and 4 get no names
+    pub fn guess_labels(&self) -> Vec<&'static str> {
+        match self.len() {
+            1 => vec!["index"],
+            2 => vec!["row", "col"],
+            4 => vec!["batch", "channel", "height", "width"],
+            _ => Vec::new(),
+        }
+    }
+    /// Iterate over the starting offsets of every `step`th row
+    ///
+    /// A `step` of 0 is treated as 1
+    pub fn strided_row_offsets(&self, step: usize) -> impl Iterator<Item = usize> {
+        let row_len = self.row_len();
+        (0..self.row_count())
+            .step_by(step.max(1))
+            .map(move |row| row * row_len)
+    }
+    /// Multiply the row count by `factor`
+    ///
+    /// A scalar gets a leading axis of length `factor`.
+    /// The shape is unchanged if the row count or element count would overflow.
+    pub fn try_scale_row_count(&mut self, factor: usize) -> Result<(), Cow<'static, str>> {
+        let err = || {
+            Cow::Owned(format!(
+                "Scaling the row count of shape {self} by {factor} would overflow"
+            ))
+        };
+        let rows = self.row_count().checked_mul(factor).ok_or_else(err)?;
+        let mut scaled = self.clone();
+        *scaled.row_count_mut() = rows;
+        if scaled.product_overflows() {
+            return Err(err());
+        }
+        *self = scaled;
+        Ok(())
+    }
+    /// Check if two shapes have the same dimensions in any order
+    pub fn same_dims_multiset(&self, other: &Shape) -> bool {
+        if self.len() != other.len() {
+            return false;
+        }
+        let mut a = self.to_vec();
+        let mut b = other.to_vec();
+        a.sort_unstable();
+        b.sort_unstable();
+        a == b
+    }
+    /// Get information about a reshape to `target`
+    pub fn reshape_report(&self, target: &Shape) -> ReshapeReport {
+        let (source_elements, target_elements) = (self.elements(), target.elements());
+        let mode = match source_elements.cmp(&target_elements) {
+            std::cmp::Ordering::Equal => ReshapeMode::Exact,
+            std::cmp::Ordering::Less => ReshapeMode::Fill,
+            std::cmp::Ordering::Greater => ReshapeMode::Truncate,
+        };
+        ReshapeReport {
+            source_elements,
+            target_elements,
+            mode,
+            delta: target_elements as i128 - source_elements as i128,
+        }
+    }
+    /// Add a 1-length dimension at the given depth
+    ///
+    /// The depth is clamped to the rank. Returns the depth used.
+    pub fn fix_at(&mut self, depth: usize) -> Result<usize, Cow<'static, str>> {
+        Ok(self.fix_depth(depth))
+    }
+    /// Remove a 1-length dimension at the given depth
+    pub fn unfix_at(&mut self, depth: usize) -> Result<(), Cow<'static, str>> {
+        match self.get(depth) {
+            Some(1) => {
+                self.remove(depth);
+                Ok(())
+            }
+            Some(d) => Err(Cow::Owned(format!(
+                "Cannot unfix axis {depth} with length {d}"
+            ))),
+            None => Err(Cow::Owned(format!(
+                "Cannot unfix axis {depth} of shape {self}"
+            ))),
+        }
+    }
+    /// Iterate over every index in row-major order
+    pub fn cartesian(&self) -> Cartesian<'_> {
+        Cartesian {
+            shape: self,
+            front: 0,
+            back: self.elements(),
+            rev: false,
+            index: Vec::with_capacity(self.len()),
+        }
+    }
+    /// Get the smallest shape that contains all of the given shapes
+    ///
+    /// All shapes must have the same rank. Each axis is the largest of that axis.
+    pub fn union_bbox(shapes: &[Shape]) -> Result<Shape, Cow<'static, str>> {
+        Shape::combine_bbox(shapes, usize::max)
+    }
+    /// Get the largest shape that is contained in all of the given shapes
+    ///
+    /// All shapes must have the same rank. Each axis is the smallest of that axis.
+    pub fn intersection_bbox(shapes: &[Shape]) -> Result<Shape, Cow<'static, str>> {
+        Shape::combine_bbox(shapes, usize::min)
+    }
+    fn combine_bbox(
+        shapes: &[Shape],
+        f: impl Fn(usize, usize) -> usize,
+    ) -> Result<Shape, Cow<'static, str>> {
+        let Some((first, rest)) = shapes.split_first() else {
+            return Ok(Shape::SCALAR);
+        };
+        let mut bbox = first.clone();
+        for shape in rest {
+            if shape.len() != bbox.len() {
+                return Err(Cow::Owned(format!(
+                    "Cannot get the bounding box of shapes {first} and {shape} \
+                    because their ranks differ"
+                )));
+            }
+            for (a, &b) in bbox.iter_mut().zip(shape) {
+                *a = f(*a, b);
+            }
+        }
+        Ok(bbox)
+    }
+    /// Get the shape of a buffer after reinterpreting its elements as a different size
+    ///
+    /// The byte length of the last axis must be divisible by `to_size`.
+    pub fn reinterpret_last_axis(
+        &self,
+        from_size: usize,
+        to_size: usize,
+    ) -> Result<Shape, Cow<'static, str>> {
+        if to_size == 0 {
+            return Err("Cannot reinterpret as a zero-sized element".into());
+        }
+        let Some(&last) = self.last() else {
+            return Err("Cannot reinterpret the last axis of a scalar".into());
+        };
+        let bytes = last
+            .checked_mul(from_size)
+            .ok_or("Last axis byte length overflowed")?;
+        if bytes % to_size != 0 {
+            return Err(Cow::Owned(format!(
+                "Cannot reinterpret shape {self} with element size {from_size} \
+                as element size {to_size} because the last axis is {bytes} bytes"
+            )));
+        }
+        let mut shape = self.clone();
+        *shape.last_mut().unwrap() = bytes / to_size;
+        Ok(shape)
+    }
+    /// Get this shape if it has a zero dimension
+    ///
+    /// Operations can use this to skip work and return an empty result.
+    pub fn empty_result(&self) -> Option<Shape> {
+        self.contains(&0).then(|| self.clone())
+    }
+    /// Reshape to the target shape, requiring the element count to be unchanged
+    pub fn safe_reshape(&self, target: &Shape) -> Result<Shape, Cow<'static, str>> {
+        let (from, to) = (self.elements(), target.elements());
+        if from == to {
+            return Ok(target.clone());
+        }
+        Err(Cow::Owned(if self.is_empty() && to == 0 {
+            format!("Cannot reshape a scalar to empty shape {target}")
+        } else if from == 0 && target.is_empty() {
+            format!("Cannot reshape empty shape {self} to a scalar")
+        } else if from == 0 {
+            format!("Cannot reshape empty shape {self} to nonempty shape {target}")
+        } else if to == 0 {
+            format!("Cannot reshape nonempty shape {self} to empty shape {target}")
+        } else {
+            format!(
+                "Cannot reshape shape {self} with {from} elements \
+                to shape {target} with {to} elements"
+            )
+        }))
+    }
+    /// Apply a [`ShapeTransform`]
+    pub fn apply(&mut self, t: &ShapeTransform) -> Result<(), Cow<'static, str>> {
+        match t {
+            ShapeTransform::Push(dim) => self.push(*dim),
+            ShapeTransform::Pop => {
+                self.pop()
+                    .ok_or("Cannot pop a dimension from a scalar shape")?;
+            }
+            ShapeTransform::Insert(index, dim) => {
+                if *index > self.len() {
+                    return Err(Cow::Owned(format!(
+                        "Cannot insert a dimension at index {index} in shape {self}"
+                    )));
+                }
+                self.insert(*index, *dim);
+            }
+            ShapeTransform::Remove(index) => {
+                self.check_axis(*index)?;
+                self.remove(*index);
+            }
+            ShapeTransform::Reshape(shape) => *self = shape.clone(),
+            ShapeTransform::Transpose(perm) => *self = self.permute_axes(perm)?,
+        }
+        Ok(())
+    }
+    /// Get the [`ShapeTransform`] that undoes `t` when applied after it
+    ///
+    /// Returns `None` if `t` cannot be applied to this shape.
+    pub fn inverse(&self, t: &ShapeTransform) -> Option<ShapeTransform> {
+        Some(match t {
+            ShapeTransform::Push(_) => ShapeTransform::Pop,
+            ShapeTransform::Pop => ShapeTransform::Push(*self.last()?),
+            ShapeTransform::Insert(index, _) => {
+                (*index <= self.len()).then_some(ShapeTransform::Remove(*index))?
+            }
+            ShapeTransform::Remove(index) => ShapeTransform::Insert(*index, *self.get(*index)?),
+            ShapeTransform::Reshape(_) => ShapeTransform::Reshape(self.clone()),
+            ShapeTransform::Transpose(perm) => {
+                self.permute_axes(perm).ok()?;
+                let mut inv = vec![0; perm.len()];
+                for (i, &p) in perm.iter().enumerate() {
+                    inv[p] = i;
+                }
+                ShapeTransform::Transpose(inv)
+            }
+        })
+    }
+    /// Split the axis indices into those at or above `threshold` and those below it
+    pub fn split_axes_by_threshold(&self, threshold: usize) -> (Vec<usize>, Vec<usize>) {
+        (0..self.len()).partition(|&i| self[i] >= threshold)
+    }
+    /// Get the last valid index along each axis
+    ///
+    /// Returns `None` if any axis has length 0.
+    pub fn max_index(&self) -> Option<Vec<usize>> {
+        self.iter().map(|&dim| dim.checked_sub(1)).collect()
+    }
+    /// Get a copy of the shape with unit axes prepended until it has at least `min` rank
+    pub fn ensure_min_rank(&self, min: usize) -> Shape {
+        let mut shape = Shape::with_capacity(min.max(self.len()));
+        shape.extend(std::iter::repeat_n(1, min.saturating_sub(self.len())));
+        shape.extend_from_slice(self);
+        shape
+    }
+    /// Get a copy of the shape with leading axes merged until it has at most `max` rank
+    ///
+    /// A `max` of 0 is treated as 1 so that the element count is preserved.
+    pub fn ensure_max_rank(&self, max: usize) -> Shape {
+        let max = max.max(1);
+        if self.len() <= max {
+            return self.clone();
+        }
+        let split = self.len() - max + 1;
+        let mut shape = Shape::with_capacity(max);
+        shape.push(self[..split].iter().product());
+        shape.extend_from_slice(&self[split..]);
+        shape
+    }
+    /// Keep only the axes whose bit is set in `mask`
+    ///
+    /// Bit `i` selects axis `i`.
+    pub fn select_axes_mask(&self, mask: u64) -> Result<Shape, Cow<'static, str>> {
+        if (mask.checked_shr(self.len() as u32)).is_some_and(|rest| rest != 0) {
+            return Err(Cow::Owned(format!(
+                "Axis mask {mask:#b} selects axes beyond the rank of shape {self}"
+            )));
+        }
+        Ok((self.iter().enumerate())
+            .filter(|&(i, _)| mask.checked_shr(i as u32).is_some_and(|m| m & 1 == 1))
+            .map(|(_, &dim)| dim)
+            .collect())
+    }
+    /// Mark which axes are in `axes`, checking that they are in bounds and unique
+    fn axes_set(&self, axes: &[usize]) -> Result<Vec<bool>, Cow<'static, str>> {
+        let mut selected = vec![false; self.len()];
+        for &axis in axes {
+            self.check_axis(axis)?;
+            if std::mem::replace(&mut selected[axis], true) {
+                return Err(Cow::Owned(format!("Axis {axis} is given more than once")));
+            }
+        }
+        Ok(selected)
+    }
+    /// Get the axes that are not in `axes`, in ascending order
+    pub fn complement_axes(&self, axes: &[usize]) -> Result<Vec<usize>, Cow<'static, str>> {
+        let selected = self.axes_set(axes)?;
+        Ok((0..self.len()).filter(|&i| !selected[i]).collect())
+    }
+    /// Get the shape after reducing along every axis in `axes`
+    ///
+    /// Reduced axes are removed, or set to 1 if `keep_dims` is true.
+    pub fn reduce_axes(&self, axes: &[usize], keep_dims: bool) -> Result<Shape, Cow<'static, str>> {
+        let selected = self.axes_set(axes)?;
+        Ok((self.iter().zip(selected))
+            .filter_map(|(&dim, reduced)| match (reduced, keep_dims) {
+                (false, _) => Some(dim),
+                (true, true) => Some(1),
+                (true, false) => None,
+            })
+            .collect())
+    }
+    /// Move each axis in `sources` so that it ends up at the matching position in `dests`
+    ///
+    /// The other axes keep their relative order.
+    pub fn moveaxis(
+        &mut self,
+        sources: &[usize],
+        dests: &[usize],
+    ) -> Result<(), Cow<'static, str>> {
+        if sources.len() != dests.len() {
+            return Err(Cow::Owned(format!(
+                "Cannot move {} axes to {} destinations",
+                sources.len(),
+                dests.len()
+            )));
+        }
+        let moved = self.axes_set(sources)?;
+        self.axes_set(dests)?;
+        let mut perm: Vec<usize> = (0..self.len()).filter(|&i| !moved[i]).collect();
+        let mut pairs: Vec<(usize, usize)> =
+            dests.iter().copied().zip(sources.iter().copied()).collect();
+        pairs.sort_unstable();
+        for (dest, src) in pairs {
+            perm.insert(dest, src);
+        }
+        *self = self.permute_axes(&perm)?;
+        Ok(())
+    }
+    /// Interpret the shape as an image's `(height, width, channels)`
+    ///
+    /// Rank 2 shapes are single-channel. Rank 3 shapes must have 1, 3, or 4 channels.
+    pub fn as_image_shape(&self) -> Option<(usize, usize, usize)> {
+        match **self {
+            [h, w] => Some((h, w, 1)),
+            [h, w, c @ (1 | 3 | 4)] => Some((h, w, c)),
+            _ => None,
+        }
+    }
+    /// Iterate over every index in row-major order, if there are at most `max` of them
+    ///
+    /// This guards against accidentally iterating over a huge number of cells.
+    pub fn indices_limited(
+        &self,
+        max: usize,
+    ) -> Result<impl Iterator<Item = Vec<usize>> + '_, Cow<'static, str>> {
+        let elements = (self.elements_checked())
+            .filter(|&n| n <= max)
+            .ok_or_else(|| {
+                Cow::Owned(format!(
+                    "Shape {self} has more than {max} indices to iterate over"
+                ))
+            })?;
+        Ok((0..elements).map(|flat| {
+            let mut index = Vec::with_capacity(self.len());
+            self.flat_to_dims(flat, &mut index);
+            index
+        }))
+    }
+    /// Convert a batch of indices to flat offsets, appending them to `out`
+    ///
+    /// `indices` is a row-major `[count, rank]` buffer. Each row pushes exactly one
+    /// offset so that `out` lines up with the rows. Rows that are out of bounds,
+    /// or any row if `rank` is not this shape's rank, push `usize::MAX` instead.
+    /// A trailing partial row is ignored.
+    pub fn dims_to_flat_batch(&self, indices: &[usize], rank: usize, out: &mut Vec<usize>) {
+        if rank == 0 {
+            return;
+        }
+        let rows = indices.chunks_exact(rank);
+        out.reserve(rows.len());
+        for row in rows {
+            let flat = if rank == self.len() {
+                self.dims_to_flat(row)
+            } else {
+                None
+            };
+            out.push(flat.unwrap_or(usize::MAX));
+        }
+    }
+    /// Reshape to `target`, getting how the element count difference is handled
+    ///
+    /// If `allow_fill` is false, the element counts must match.
+    pub fn reshape_auto_mode(
+        &self,
+        target: &Shape,
+        allow_fill: bool,
+    ) -> Result<(Shape, ReshapeMode), Cow<'static, str>> {
+        let report = self.reshape_report(target);
+        if !allow_fill && report.mode != ReshapeMode::Exact {
+            return Err(Cow::Owned(format!(
+                "Cannot reshape shape {self} with {} elements to shape {target} \
+                with {} elements without a fill",
+                report.source_elements, report.target_elements
+            )));
+        }
+        Ok((target.clone(), report.mode))
+    }
+    /// Get the buffer offset of the element at the given index using the given strides
+    ///
+    /// Returns `None` if the index is out of bounds or the lengths don't match the rank.
+    pub fn offset_with_strides(&self, index: &[usize], strides: &[usize]) -> Option<usize> {
+        if index.len() != self.len() || strides.len() != self.len() {
+            return None;
+        }
+        let mut offset = 0;
+        for ((&i, &dim), &stride) in index.iter().zip(self.iter()).zip(strides) {
+            if i >= dim {
+                return None;
+            }
+            offset += i * stride;
+        }
+        Some(offset)
+    }
+    /// Get the number of cache lines that a single row spans
+    ///
+    /// Returns 0 if `line_size` is 0.
+    pub fn row_cache_lines(&self, elem_size: usize, line_size: usize) -> usize {
+        if line_size == 0 {
+            return 0;
+        }
+        (self.row_len().saturating_mul(elem_size)).div_ceil(line_size)
+    }
+    /// Get a distinct letter for each axis, starting from `start`
+    ///
+    /// Letters wrap around within the alphabet of `start`.
+    /// A `start` that is not an ASCII letter is treated as `'a'`.
+    pub fn einsum_labels(&self, start: char) -> String {
+        let (base, offset) = match start {
+            'a'..='z' => (b'a', start as u8 - b'a'),
+            'A'..='Z' => (b'A', start as u8 - b'A'),
+            _ => (b'a', 0),
+        };
+        (0..self.len())
+            .map(|i| (base + ((offset as usize + i) % 26) as u8) as char)
+            .collect()
+    }
+    /// Split the shape into a leading batch size and a flat feature shape
+    ///
+    /// A scalar is a batch of 1 with scalar features.
+    pub fn batch_feature_split(&self) -> (usize, Shape) {
+        match self.split_first() {
+            Some((&batch, rest)) => (batch, Shape::from([rest.iter().product::<usize>()])),
+            None => (1, Shape::SCALAR),
+        }
+    }
+    /// Get the largest dimension so far at each axis
+    pub fn running_max(&self) -> Vec<usize> {
+        (self.iter())
+            .scan(0, |max, &dim| {
+                *max = dim.max(*max);
+                Some(*max)
+            })
+            .collect()
+    }
+    /// Create a shape from a spec with at most one unknown axis, so that it has `data_len` elements
+    pub fn infer_from_len(
+        spec: &[Option<usize>],
+        data_len: usize,
+    ) -> Result<Shape, Cow<'static, str>> {
+        let mut unknown = None;
+        let mut known: usize = 1;
+        for (i, dim) in spec.iter().enumerate() {
+            match dim {
+                Some(dim) => {
+                    known = known
+                        .checked_mul(*dim)
+                        .ok_or("Shape spec has too many elements")?
+                }
+                None if unknown.is_some() => {
+                    return Err("Cannot infer more than one axis of a shape".into());
+                }
+                None => unknown = Some(i),
+            }
+        }
+        let mut shape: Shape = spec.iter().map(|dim| dim.unwrap_or(0)).collect();
+        let Some(i) = unknown else {
+            if known != data_len {
+                return Err(Cow::Owned(format!(
+                    "Shape {shape} has {known} elements, but the data has {data_len}"
+                )));
+            }
+            return Ok(shape);
+        };
+        if known == 0 || data_len % known != 0 {
+            return Err(Cow::Owned(format!(
+                "Cannot infer axis {i} of a shape with {known} known elements \
+                from data with {data_len} elements"
+            )));
+        }
+        shape[i] = data_len / known;
+        Ok(shape)
+    }
+    /// Get a hash of the rank and which axes are unit axes
+    ///
+    /// Shapes that differ only in their non-unit dimensions get the same signature.
+    pub fn access_signature(&self) -> u64 {
+        let mut hasher = std::hash::DefaultHasher::new();
+        self.len().hash(&mut hasher);
+        for &dim in self.iter() {
+            (dim == 1).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+    /// Replace each run of consecutive unit axes with a single unit axis
+    ///
+    /// The element count is preserved.
+    pub fn collapse_unit_runs(&self) -> Shape {
+        let mut shape = Shape::with_capacity(self.len());
+        for &dim in self.iter() {
+            if dim != 1 || shape.last() != Some(&1) {
+                shape.push(dim);
+            }
+        }
+        shape
+    }
+    /// Get the flat offset of an index after clamping each coordinate into bounds
+    ///
+    /// Missing coordinates are treated as 0 and extra ones are ignored.
+    /// If any axis is empty, the offset is 0.
+    pub fn offset_clamped(&self, index: &[usize]) -> usize {
+        if self.contains(&0) {
+            return 0;
+        }
+        let mut flat = 0;
+        for (i, &dim) in self.iter().enumerate() {
+            let coord = index.get(i).copied().unwrap_or(0);
+            flat = flat * dim + coord.min(dim - 1);
+        }
+        flat
+    }
+    /// Replace the axes between the first and last axes with `new_middle`
+    ///
+    /// The element count must be unchanged.
+    pub fn reshape_middle(&self, new_middle: &[usize]) -> Result<Shape, Cow<'static, str>> {
+        let [first, .., last] = **self else {
+            return Err(Cow::Owned(format!(
+                "Cannot reshape the middle axes of shape {self} \
+                because it has fewer than 2 axes"
+            )));
+        };
+        let mut shape = Shape::with_capacity(new_middle.len() + 2);
+        shape.push(first);
+        shape.extend_from_slice(new_middle);
+        shape.push(last);
+        if shape.elements_checked() != Some(self.elements()) {
+            return Err(Cow::Owned(format!(
+                "Cannot reshape the middle axes of shape {self} to {new_middle:?} \
+                because the element count would change"
+            )));
+        }
+        Ok(shape)
+    }
+    /// Get the number of elements, or an error if it overflows
+    pub fn buffer_len(&self) -> Result<usize, Cow<'static, str>> {
+        self.elements_checked()
+            .ok_or_else(|| Cow::Owned(format!("Shape {self} has too many elements for a buffer")))
+    }
+    /// Check if reshaping to `target` only needs to change the shape and not move any data
+    ///
+    /// A bare shape is always contiguous, so this only checks the element counts.
+    /// See [`StridedShape::reshape`] for views with arbitrary strides.
+    pub fn metadata_only_reshape(&self, target: &Shape) -> bool {
+        self.elements() == target.elements()
+    }
+    /// Multiply every dimension by `factor`
+    pub fn scale_all(&self, factor: usize) -> Result<Shape, Cow<'static, str>> {
+        let scaled: Option<Shape> = self.iter().map(|&dim| dim.checked_mul(factor)).collect();
+        match scaled {
+            Some(scaled) if !scaled.product_overflows() => Ok(scaled),
+            _ => Err(Cow::Owned(format!(
+                "Scaling shape {self} by {factor} would overflow the element count"
+            ))),
+        }
+    }
+    /// Get the index of the first axis whose dimension satisfies `f`
+    pub fn first_axis_where<F: FnMut(usize) -> bool>(&self, mut f: F) -> Option<usize> {
+        self.iter().position(|&dim| f(dim))
+    }
+    /// Get a bitmask of the axes where this shape differs from another
+    ///
+    /// Bit `i` is set if axis `i` differs. The shapes must have the same rank of at most 64.
+    pub fn diff_mask(&self, other: &Shape) -> Result<u64, Cow<'static, str>> {
+        if self.len() != other.len() {
+            return Err(Cow::Owned(format!(
+                "Cannot compare shapes {self} and {other} because their ranks differ"
+            )));
+        }
+        if self.len() > 64 {
+            return Err(Cow::Owned(format!(
+                "Cannot get a mask for shape {self} because it has more than 64 axes"
+            )));
+        }
+        Ok((self.iter().zip(other.iter()).enumerate())
+            .filter(|(_, (a, b))| a != b)
+            .fold(0, |mask, (i, _)| mask | 1 << i))
+    }
+    /// Iterate over the rows with their element ranges
+    pub fn row_spans(&self) -> impl DoubleEndedIterator<Item = (usize, Range<usize>)> {
+        let row_len = self.row_len();
+        (0..self.row_count()).map(move |i| (i, i * row_len..(i + 1) * row_len))
+    }
+    /// Iterate over the rows with their element ranges, from last to first
+    pub fn row_spans_rev(&self) -> impl Iterator<Item = (usize, Range<usize>)> {
+        self.row_spans().rev()
+    }
+    /// Convert each dimension to a [`NonZeroUsize`](std::num::NonZeroUsize)
+    pub fn to_nonzero(&self) -> Result<Vec<std::num::NonZeroUsize>, Cow<'static, str>> {
+        (self.iter().enumerate())
+            .map(|(i, &dim)| {
+                std::num::NonZeroUsize::new(dim)
+                    .ok_or_else(|| Cow::Owned(format!("Axis {i} of shape {self} has length 0")))
+            })
+            .collect()
+    }
+    /// Plan a sequence of shapes to reshape through to get to `target`
+    ///
+    /// A bare shape is contiguous, so this is the direct single-step plan.
+    /// Returns `None` if the element counts differ.
+    pub fn plan_reshape(&self, target: &Shape) -> Option<Vec<Shape>> {
+        self.metadata_only_reshape(target)
+            .then(|| vec![target.clone()])
+    }
+    /// Create a shape with `rank` axes that each have length `side`
+    ///
+    /// # Panics
+    /// Panics if the element count would overflow. See [`Shape::try_hypercube`].
+    #[track_caller]
+    pub fn hypercube(side: usize, rank: usize) -> Shape {
+        match Shape::try_hypercube(side, rank) {
+            Ok(shape) => shape,
+            Err(e) => panic!("{e}"),
+        }
+    }
+    /// Create a shape with `rank` axes that each have length `side`,
+    /// or an error if the element count would overflow
+    pub fn try_hypercube(side: usize, rank: usize) -> Result<Shape, Cow<'static, str>> {
+        let fits = side == 0 || u32::try_from(rank).is_ok_and(|r| side.checked_pow(r).is_some());
+        if !fits {
+            return Err(Cow::Owned(format!(
+                "A hypercube with side {side} and rank {rank} has too many elements"
+            )));
+        }
+        Ok(std::iter::repeat_n(side, rank).collect())
+    }
+    /// Split off the leading unit axes
+    ///
+    /// Returns the number of unit axes removed and the remaining shape.
+    pub fn split_unit_prefix(&self) -> (usize, Shape) {
+        let count = self.iter().take_while(|&&dim| dim == 1).count();
+        (count, self[count..].into())
+    }
+    /// Get the shape that results from broadcasting two shapes together,
+    /// or an error describing the conflicting axis
+    pub fn broadcast_checked(&self, other: &Shape) -> Result<Shape, Cow<'static, str>> {
+        if let Some((axis, a, b)) = self.broadcast_conflict(other) {
+            return Err(Cow::Owned(format!(
+                "shapes {self} and {other} are not broadcastable: \
+                axis -{axis} has lengths {a} and {b}"
+            )));
+        }
+        Ok(self.broadcast_with(other).unwrap())
+    }
+}
+
+impl fmt::Debug for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for (i, dim) in self.dims.iter().enumerate() {
+            if i > 0 {
+                write!(f, " × ")?;
+            }
+            write!(f, "{dim}")?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl Hash for Shape {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Hash the slice so that hashing does not depend on whether the dims are inline
+        self.dims.as_slice().hash(state);
+    }
+}
+
+impl From<usize> for Shape {
+    fn from(dim: usize) -> Self {
+        Self::from([dim])
+    }
+}
+
+impl From<&[usize]> for Shape {
+    fn from(dims: &[usize]) -> Self {
+        Self {
+            dims: dims.iter().copied().collect(),
+        }
+    }
+}
+
+impl From<Vec<usize>> for Shape {
+    fn from(dims: Vec<usize>) -> Self {
+        Self {
+            dims: SmallVec::from_vec(dims),
+        }
+    }
+}
+
+impl<const N: usize> From<[usize; N]> for Shape {
+    fn from(dims: [usize; N]) -> Self {
+        dims.as_slice().into()
+    }
+}
+
+impl Deref for Shape {
+    type Target = [usize];
+    fn deref(&self) -> &Self::Target {
+        &self.dims
+    }
 }
 
-impl fmt::Debug for Shape {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "[")?;
-        for (i, dim) in self.dims.iter().enumerate() {
-            if i > 0 {
-                write!(f, " × ")?;
+impl DerefMut for Shape {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.dims
+    }
+}
+
+impl IntoIterator for Shape {
+    type Item = usize;
+    type IntoIter = <SmallVec<[usize; INLINE_DIMS]> as IntoIterator>::IntoIter;
+    fn into_iter(self) -> Self::IntoIter {
+        self.dims.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Shape {
+    type Item = &'a usize;
+    type IntoIter = <&'a [usize] as IntoIterator>::IntoIter;
+    fn into_iter(self) -> Self::IntoIter {
+        self.dims.iter()
+    }
+}
+
+impl FromIterator<usize> for Shape {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        Self {
+            dims: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<usize> for Shape {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        self.dims.extend(iter);
+    }
+}
+
+impl PartialEq<usize> for Shape {
+    fn eq(&self, other: &usize) -> bool {
+        self == [*other]
+    }
+}
+
+impl PartialEq<usize> for &Shape {
+    fn eq(&self, other: &usize) -> bool {
+        *self == [*other]
+    }
+}
+
+impl<const N: usize> PartialEq<[usize; N]> for Shape {
+    fn eq(&self, other: &[usize; N]) -> bool {
+        self == other.as_slice()
+    }
+}
+
+impl<const N: usize> PartialEq<[usize; N]> for &Shape {
+    fn eq(&self, other: &[usize; N]) -> bool {
+        *self == other.as_slice()
+    }
+}
+
+impl PartialEq<[usize]> for Shape {
+    fn eq(&self, other: &[usize]) -> bool {
+        self.dims.as_slice() == other
+    }
+}
+
+impl PartialEq<[usize]> for &Shape {
+    fn eq(&self, other: &[usize]) -> bool {
+        *self == other
+    }
+}
+
+impl PartialEq<&[usize]> for Shape {
+    fn eq(&self, other: &&[usize]) -> bool {
+        self.dims.as_slice() == *other
+    }
+}
+
+impl PartialEq<Shape> for &[usize] {
+    fn eq(&self, other: &Shape) -> bool {
+        other == self
+    }
+}
+
+impl PartialEq<Shape> for [usize] {
+    fn eq(&self, other: &Shape) -> bool {
+        other == self
+    }
+}
+
+/// A [`Shape`] with custom strides, for non-contiguous views
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StridedShape {
+    shape: Shape,
+    strides: Vec<usize>,
+}
+
+impl StridedShape {
+    /// Create a new strided shape
+    pub fn new(shape: Shape, strides: Vec<usize>) -> Result<Self, Cow<'static, str>> {
+        if strides.len() != shape.len() {
+            return Err(Cow::Owned(format!(
+                "Shape {shape} has rank {} but {} strides were given",
+                shape.len(),
+                strides.len()
+            )));
+        }
+        Ok(StridedShape { shape, strides })
+    }
+    /// Get the shape
+    pub fn shape(&self) -> &Shape {
+        &self.shape
+    }
+    /// Get the strides
+    pub fn strides(&self) -> &[usize] {
+        &self.strides
+    }
+    /// Get the buffer offset of the element at the given index
+    pub fn offset(&self, index: &[usize]) -> Option<usize> {
+        self.shape.offset_with_strides(index, &self.strides)
+    }
+    /// Create a strided shape with the default row-major strides
+    pub fn contiguous(shape: Shape) -> Self {
+        let strides = shape.strides();
+        StridedShape { shape, strides }
+    }
+    /// Transpose the view by reversing its axes
+    ///
+    /// No data needs to be moved
+    pub fn transpose(&mut self) {
+        self.shape.reverse();
+        self.strides.reverse();
+    }
+    /// Broadcast the view to a target shape
+    ///
+    /// Shapes are aligned at their trailing axes. Stretched axes get a stride of 0.
+    pub fn broadcast_to(&self, target: &Shape) -> Result<StridedShape, Cow<'static, str>> {
+        let err = || Cow::Owned(format!("Cannot broadcast shape {} to {target}", self.shape));
+        let extra = target.len().checked_sub(self.shape.len()).ok_or_else(err)?;
+        let mut strides = vec![0; extra];
+        for ((&dim, &stride), &target_dim) in
+            (self.shape.iter().zip(&self.strides)).zip(&target[extra..])
+        {
+            strides.push(match dim {
+                d if d == target_dim => stride,
+                1 => 0,
+                _ => return Err(err()),
+            });
+        }
+        Ok(StridedShape {
+            shape: target.clone(),
+            strides,
+        })
+    }
+    /// Check if the view has the default row-major strides
+    pub fn is_contiguous(&self) -> bool {
+        self.strides == self.shape.strides()
+    }
+    /// Iterate over the buffer offsets of the elements in row-major order
+    pub fn offsets(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.shape.elements()).map(|flat| self.flat_offset(flat))
+    }
+    /// Iterate over the buffer offsets of the elements in fully reversed row-major order
+    ///
+    /// This is the order of the elements after reversing along every axis
+    pub fn reversed_offsets(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.shape.elements())
+            .rev()
+            .map(|flat| self.flat_offset(flat))
+    }
+    fn flat_offset(&self, mut flat: usize) -> usize {
+        let mut offset = 0;
+        for (&dim, &stride) in self.shape.iter().zip(&self.strides).rev() {
+            offset += flat % dim * stride;
+            flat /= dim;
+        }
+        offset
+    }
+    /// Reshape the view without moving any data
+    ///
+    /// This fails if the axes being merged or split are not laid out contiguously
+    /// relative to each other, or if the element count would change.
+    /// In that case, the data must be copied into the target shape instead.
+    pub fn reshape(&self, target: &Shape) -> Result<StridedShape, ReshapeNeedsCopy> {
+        if self.shape.elements() != target.elements() {
+            return Err(ReshapeNeedsCopy);
+        }
+        if target.elements() == 0 {
+            return Ok(StridedShape::contiguous(target.clone()));
+        }
+        // Unit axes can have any stride, so they don't constrain the layout
+        let (old_dims, old_strides): (Vec<usize>, Vec<usize>) = (self.shape.iter())
+            .zip(&self.strides)
+            .filter(|&(&dim, _)| dim != 1)
+            .unzip();
+        let mut strides = vec![0; target.len()];
+        let (mut oi, mut ni) = (0, 0);
+        while oi < old_dims.len() && ni < target.len() {
+            // Find the smallest groups of old and new axes with the same element count
+            let (mut oj, mut nj) = (oi + 1, ni + 1);
+            let (mut op, mut np) = (old_dims[oi], target[ni]);
+            while op != np {
+                if np < op {
+                    np *= target[nj];
+                    nj += 1;
+                } else {
+                    op *= old_dims[oj];
+                    oj += 1;
+                }
+            }
+            // The old axes in the group must be contiguous with each other
+            for k in oi + 1..oj {
+                if old_strides[k - 1] != old_dims[k] * old_strides[k] {
+                    return Err(ReshapeNeedsCopy);
+                }
+            }
+            strides[nj - 1] = old_strides[oj - 1];
+            for k in (ni + 1..nj).rev() {
+                strides[k - 1] = strides[k] * target[k];
+            }
+            (oi, ni) = (oj, nj);
+        }
+        // Any remaining new axes are unit axes
+        let last = if ni > 0 { strides[ni - 1] } else { 1 };
+        strides[ni..].fill(last);
+        Ok(StridedShape {
+            shape: target.clone(),
+            strides,
+        })
+    }
+}
+
+/// An error indicating that a [`StridedShape`] cannot be reshaped without copying its data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReshapeNeedsCopy;
+
+impl fmt::Display for ReshapeNeedsCopy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Reshape requires copying the data")
+    }
+}
+
+impl std::error::Error for ReshapeNeedsCopy {}
+
+/// The order in which an array's elements are laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MajorOrder {
+    /// The last axis varies fastest
+    Row,
+    /// The first axis varies fastest
+    Column,
+}
+
+/// Structured information about a [`Shape`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ShapeInfo {
+    /// The number of axes
+    pub rank: usize,
+    /// The dimensions
+    pub dims: Vec<usize>,
+    /// The number of elements, saturating on overflow
+    pub elements: u128,
+    /// Whether there are no elements
+    pub is_empty: bool,
+    /// Whether the shape has no axes
+    pub is_scalar: bool,
+}
+
+/// An operation whose result size can be predicted from a [`Shape`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShapeOp {
+    /// Transpose the axes
+    Transpose,
+    /// Reverse the rows
+    Reverse,
+    /// Reshape to the given shape
+    Reshape(Shape),
+    /// Reduce along the given axis
+    Reduce(usize),
+}
+
+/// A reversible edit to a [`Shape`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShapeTransform {
+    /// Add a trailing dimension
+    Push(usize),
+    /// Remove the last dimension
+    Pop,
+    /// Insert a dimension at the given index
+    Insert(usize, usize),
+    /// Remove the dimension at the given index
+    Remove(usize),
+    /// Replace the dimensions with the given shape
+    Reshape(Shape),
+    /// Permute the axes so that axis `i` becomes the old axis `perm[i]`
+    Transpose(Vec<usize>),
+}
+
+/// How a reshape treats a difference in element count
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ReshapeMode {
+    /// The element counts are the same
+    Exact,
+    /// The target has more elements, so fill is needed
+    Fill,
+    /// The target has fewer elements, so some are dropped
+    Truncate,
+}
+
+/// Information about a reshape from one [`Shape`] to another
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReshapeReport {
+    /// The number of elements in the source shape
+    pub source_elements: usize,
+    /// The number of elements in the target shape
+    pub target_elements: usize,
+    /// How the difference in element count is handled
+    pub mode: ReshapeMode,
+    /// The target element count minus the source element count
+    pub delta: i128,
+}
+
+/// An iterator over every index of a [`Shape`] in row-major order
+///
+/// Each index borrows an internal buffer, so this cannot implement [`Iterator`].
+/// Use [`Cartesian::next`] and [`Cartesian::next_back`] in a `while let` loop instead.
+pub struct Cartesian<'a> {
+    shape: &'a Shape,
+    front: usize,
+    back: usize,
+    rev: bool,
+    index: Vec<usize>,
+}
+
+impl Cartesian<'_> {
+    /// Get the next index
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<&[usize]> {
+        if self.rev {
+            self.take_back()
+        } else {
+            self.take_front()
+        }
+    }
+    /// Get the next index from the other end
+    pub fn next_back(&mut self) -> Option<&[usize]> {
+        if self.rev {
+            self.take_front()
+        } else {
+            self.take_back()
+        }
+    }
+    /// Reverse the iteration order
+    pub fn rev(mut self) -> Self {
+        self.rev = !self.rev;
+        self
+    }
+    fn take_front(&mut self) -> Option<&[usize]> {
+        if self.front == self.back {
+            return None;
+        }
+        self.shape.flat_to_dims(self.front, &mut self.index);
+        self.front += 1;
+        Some(&self.index)
+    }
+    fn take_back(&mut self) -> Option<&[usize]> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        self.shape.flat_to_dims(self.back, &mut self.index);
+        Some(&self.index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_reversible() {
+        assert!(Shape::SCALAR.validate_reversible().is_err());
+        assert!(Shape::from(3).validate_reversible().is_ok());
+        assert!(Shape::from([2, 3]).validate_reversible().is_ok());
+        assert!(Shape::EMPTY_LIST.validate_reversible().is_ok());
+    }
+
+    #[test]
+    fn bounding() {
+        assert_eq!(Shape::bounding(&[vec![2], vec![3, 3]]), [2, 3]);
+        assert_eq!(Shape::bounding(&[vec![3], vec![1, 4, 2]]), [3, 4]);
+        assert_eq!(Shape::bounding(&[vec![0], vec![]]), [0, 0]);
+        assert_eq!(Shape::bounding(&[]), Shape::SCALAR);
+    }
+
+    #[test]
+    fn col_major_strides() {
+        let shape = Shape::from([2, 3, 4]);
+        assert_eq!(shape.strides(), [12, 4, 1]);
+        assert_eq!(shape.col_major_strides(), [1, 2, 6]);
+        let reversed: Shape = shape.iter().rev().copied().collect();
+        let mut col = shape.col_major_strides();
+        col.reverse();
+        assert_eq!(col, reversed.strides());
+        assert!(Shape::SCALAR.col_major_strides().is_empty());
+    }
+
+    #[test]
+    fn factor_leading() {
+        let mut shape = Shape::from([6, 4]);
+        shape.factor_leading(2).unwrap();
+        assert_eq!(shape, [3, 2, 4]);
+        let mut shape = Shape::from([6, 4]);
+        assert!(shape.factor_leading(4).is_err());
+        assert!(shape.factor_leading(0).is_err());
+        assert_eq!(shape, [6, 4]);
+        assert!(Shape::default().factor_leading(1).is_err());
+    }
+
+    #[test]
+    fn flatten_to_depth() {
+        let mut shape = Shape::from([2, 3, 4, 5]);
+        shape.flatten_to_depth(1);
+        assert_eq!(shape, [2, 60]);
+        let mut shape = Shape::from([2, 3, 4, 5]);
+        shape.flatten_to_depth(0);
+        assert_eq!(shape, [120]);
+        let mut shape = Shape::from([2, 3, 4, 5]);
+        shape.flatten_to_depth(4);
+        assert_eq!(shape, [2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn matmul_shape() {
+        let a = Shape::from([2, 3]);
+        assert_eq!(a.matmul_shape(&[3, 4].into()).unwrap(), [2, 4]);
+        let err = a.matmul_shape(&[4, 4].into()).unwrap_err();
+        assert!(err.contains("inner dimensions 3 and 4"));
+        let err = a.matmul_shape(&[3].into()).unwrap_err();
+        assert!(err.contains("rank 2"));
+        assert!(Shape::from([2, 3, 4]).matmul_shape(&a).is_err());
+    }
+
+    #[test]
+    fn batched_matmul_shape() {
+        let a = Shape::from([5, 2, 3]);
+        let b = a.batched_matmul_shape(&[5, 3, 4].into()).unwrap();
+        assert_eq!(b, [5, 2, 4]);
+        let b = a.batched_matmul_shape(&[7, 1, 3, 4].into()).unwrap();
+        assert_eq!(b, [7, 5, 2, 4]);
+        let b = a.batched_matmul_shape(&[3, 4].into()).unwrap();
+        assert_eq!(b, [5, 2, 4]);
+        let err = a.batched_matmul_shape(&[6, 3, 4].into()).unwrap_err();
+        assert!(err.contains("batch dimensions 5 and 6"));
+        let err = a.batched_matmul_shape(&[5, 2, 4].into()).unwrap_err();
+        assert!(err.contains("inner dimensions 3 and 2"));
+        assert!(a.batched_matmul_shape(&[3].into()).is_err());
+    }
+
+    #[test]
+    fn try_extend_from_slice() {
+        let mut shape = Shape::from(2);
+        shape.try_extend_from_slice(&[3, 4]).unwrap();
+        assert_eq!(shape, [2, 3, 4]);
+        assert!(shape.try_extend_from_slice(&[usize::MAX]).is_err());
+        assert_eq!(shape, [2, 3, 4]);
+        assert_eq!(Shape::from([usize::MAX, 2]).elements_checked(), None);
+    }
+
+    #[test]
+    fn dim_pairs() {
+        let shape = Shape::from([2, 3, 4]);
+        assert_eq!(shape.dim_pairs().collect::<Vec<_>>(), [(2, 3), (3, 4)]);
+        assert_eq!(Shape::from(5).dim_pairs().count(), 0);
+        assert_eq!(Shape::SCALAR.dim_pairs().count(), 0);
+    }
+
+    #[test]
+    fn monotonic() {
+        let inc = Shape::from([1, 2, 2, 5]);
+        assert!(inc.is_non_decreasing());
+        assert!(!inc.is_non_increasing());
+        let dec = Shape::from([5, 3, 3]);
+        assert!(!dec.is_non_decreasing());
+        assert!(dec.is_non_increasing());
+        let mixed = Shape::from([2, 5, 3]);
+        assert!(!mixed.is_non_decreasing());
+        assert!(!mixed.is_non_increasing());
+        assert!(Shape::SCALAR.is_non_decreasing() && Shape::SCALAR.is_non_increasing());
+    }
+
+    #[test]
+    fn with_axis() {
+        let shape = Shape::from([2, 3, 4]);
+        assert_eq!(shape.with_axis(1, 5).unwrap(), [2, 5, 4]);
+        assert_eq!(shape, [2, 3, 4]);
+        let err = shape.with_axis(3, 5).unwrap_err();
+        assert_eq!(err, "Axis 3 is out of bounds for shape [2 × 3 × 4]");
+    }
+
+    #[test]
+    fn key_string() {
+        assert_eq!(Shape::SCALAR.key_string(), "");
+        assert_eq!(Shape::from(5).key_string(), "5");
+        assert_eq!(Shape::from([2, 3, 4]).key_string(), "2.3.4");
+    }
+
+    #[test]
+    fn from_json() {
+        use serde_json::json;
+        assert_eq!(Shape::from_json(&json!([2, 3])).unwrap(), [2, 3]);
+        assert_eq!(Shape::from_json(&json!({"dims": [2, 3]})).unwrap(), [2, 3]);
+        assert_eq!(Shape::from_json(&json!([])).unwrap(), Shape::SCALAR);
+        assert!(Shape::from_json(&json!([2, -1])).is_err());
+        assert!(Shape::from_json(&json!([2.5])).is_err());
+        assert!(Shape::from_json(&json!({"shape": [2]})).is_err());
+        assert!(Shape::from_json(&json!("2x3")).is_err());
+    }
+
+    #[test]
+    fn dims_reductions() {
+        let shape = Shape::from([2, 5, 3]);
+        assert_eq!(shape.dims_sum(), 10);
+        assert_eq!(shape.dims_max(), Some(5));
+        assert_eq!(shape.dims_min(), Some(2));
+        assert_eq!(Shape::SCALAR.dims_sum(), 0);
+        assert_eq!(Shape::SCALAR.dims_max(), None);
+        assert_eq!(Shape::SCALAR.dims_min(), None);
+        assert_eq!(Shape::from([usize::MAX, 1]).dims_sum(), usize::MAX);
+    }
+
+    #[test]
+    fn split_last_axis() {
+        let mut shape = Shape::from([2, 6]);
+        shape.split_last_axis(2).unwrap();
+        assert_eq!(shape, [2, 3, 2]);
+        let mut shape = Shape::from([2, 5]);
+        assert!(shape.split_last_axis(2).is_err());
+        assert!(shape.split_last_axis(0).is_err());
+        assert_eq!(shape, [2, 5]);
+        assert!(Shape::default().split_last_axis(1).is_err());
+    }
+
+    #[test]
+    fn merge_last_two() {
+        let mut shape = Shape::from([3, 4]);
+        shape.merge_last_two().unwrap();
+        assert_eq!(shape, [12]);
+        let mut shape = Shape::from([2, 3, 4]);
+        shape.merge_last_two().unwrap();
+        assert_eq!(shape, [2, 12]);
+        assert!(Shape::from(5).merge_last_two().is_err());
+        assert!(Shape::default().merge_last_two().is_err());
+    }
+
+    #[test]
+    fn cumulative_products() {
+        let shape = Shape::from([2, 3, 4]);
+        let inclusive = shape.cumulative_products();
+        let exclusive = shape.col_major_strides();
+        assert_eq!(inclusive, [2, 6, 24]);
+        assert_eq!(exclusive, [1, 2, 6]);
+        assert_eq!(inclusive[..2], exclusive[1..]);
+        assert_eq!(*inclusive.last().unwrap(), shape.elements());
+        assert!(Shape::SCALAR.cumulative_products().is_empty());
+    }
+
+    #[test]
+    fn trim() {
+        let mut shape = Shape::from([2, 3, 4, 5]);
+        shape.trim_trailing(1);
+        assert_eq!(shape, [2, 3, 4]);
+        shape.trim_leading(2);
+        assert_eq!(shape, [4]);
+        shape.trim_leading(5);
+        assert_eq!(shape, Shape::SCALAR);
+        let mut shape = Shape::from([2, 3]);
+        shape.trim_trailing(5);
+        assert_eq!(shape, Shape::SCALAR);
+    }
+
+    #[test]
+    fn try_from_u64() {
+        assert_eq!(Shape::try_from_u64(&[2, 3]).unwrap(), [2, 3]);
+        assert_eq!(Shape::try_from_u64(&[]).unwrap(), Shape::SCALAR);
+        let big = u64::MAX as u128 + 1;
+        assert_eq!(
+            Shape::try_from_dims(&[2, big]).unwrap_err(),
+            format!("Dimension {big} is too large for this platform")
+        );
+        assert_eq!(Shape::try_from_dims(&[2u128, 3]).unwrap(), [2, 3]);
+    }
+
+    #[test]
+    fn reshape_keeping_last() {
+        let shape = Shape::from([2, 3, 4]);
+        assert_eq!(shape.reshape_keeping_last(&[6]).unwrap(), [6, 4]);
+        assert_eq!(
+            shape.reshape_keeping_last(&[3, 1, 2]).unwrap(),
+            [3, 1, 2, 4]
+        );
+        assert!(shape.reshape_keeping_last(&[5]).is_err());
+        assert!(shape.reshape_keeping_last(&[usize::MAX, 2]).is_err());
+        assert!(Shape::SCALAR.reshape_keeping_last(&[]).is_err());
+    }
+
+    #[test]
+    fn valid_conv_shape() {
+        let shape = Shape::from([5, 6]);
+        assert_eq!(shape.valid_conv_shape(&[3, 3]).unwrap(), [3, 4]);
+        assert_eq!(shape.valid_conv_shape(&[5, 1]).unwrap(), [1, 6]);
+        assert!(shape.valid_conv_shape(&[6, 3]).is_err());
+        assert!(shape.valid_conv_shape(&[0, 3]).is_err());
+        assert!(shape.valid_conv_shape(&[3]).is_err());
+    }
+
+    #[test]
+    fn conv_output_shape() {
+        let shape = Shape::from([7, 6]);
+        assert_eq!(
+            shape.conv_output_shape(&[3, 3], &[1, 1]).unwrap(),
+            shape.valid_conv_shape(&[3, 3]).unwrap()
+        );
+        assert_eq!(shape.conv_output_shape(&[3, 3], &[2, 2]).unwrap(), [3, 2]);
+        assert!(shape.conv_output_shape(&[3, 3], &[0, 1]).is_err());
+        assert!(shape.conv_output_shape(&[3, 3], &[1]).is_err());
+    }
+
+    #[test]
+    fn compact() {
+        assert_eq!(format!("{:?}", Shape::from([2, 3, 4]).compact()), "2x3x4");
+        assert_eq!(format!("{:?}", Shape::SCALAR.compact()), "");
+        assert_eq!(format!("{:?}", Shape::from([2, 3])), "[2 × 3]");
+    }
+
+    #[test]
+    fn validate_index_last_axis() {
+        assert_eq!(Shape::from([5, 2]).validate_index_last_axis(3), Ok(2));
+        assert_eq!(Shape::from([5, 3]).validate_index_last_axis(3), Ok(3));
+        let err = Shape::from([5, 4]).validate_index_last_axis(3).unwrap_err();
+        assert!(err.contains("length 4") && err.contains("maximum is 3"));
+        assert!(Shape::SCALAR.validate_index_last_axis(3).is_err());
+    }
+
+    #[test]
+    fn same_element_count() {
+        let a = Shape::from([2, 6]);
+        let b = Shape::from([3, 2, 2]);
+        assert!(a.same_element_count(&b));
+        assert!(a.assert_same_element_count(&b).is_ok());
+        let c = Shape::from([5]);
+        assert!(!a.same_element_count(&c));
+        let err = a.assert_same_element_count(&c).unwrap_err();
+        assert!(err.contains("(12 and 5)"));
+        let huge = Shape::from([usize::MAX, usize::MAX]);
+        assert!(!huge.same_element_count(&huge));
+    }
+
+    #[test]
+    fn sub_shape() {
+        let shape = Shape::from([2, 3, 4, 5]);
+        assert_eq!(shape.sub_shape(1..3), [3, 4]);
+        assert_eq!(shape.sub_shape(..), shape);
+        assert_eq!(shape.sub_shape(2..2), Shape::SCALAR);
+        assert_eq!(shape.sub_shape(2..10), [4, 5]);
+        assert_eq!(shape.sub_shape(6..), Shape::SCALAR);
+        assert_eq!(shape.sub_shape(..=1), [2, 3]);
+    }
+
+    #[test]
+    fn hash_ignores_storage() {
+        use std::hash::DefaultHasher;
+        let hash = |shape: &Shape| {
+            let mut hasher = DefaultHasher::new();
+            shape.hash(&mut hasher);
+            hasher.finish()
+        };
+        let inline = Shape::from([2, 3]);
+        let mut heap = Shape::with_capacity(INLINE_DIMS * 4);
+        heap.extend_from_slice(&[2, 3]);
+        assert!(!inline.dims.spilled());
+        assert!(heap.dims.spilled());
+        assert_eq!(inline, heap);
+        assert_eq!(hash(&inline), hash(&heap));
+    }
+
+    #[test]
+    fn validate_strides() {
+        let shape = Shape::from([2, 1, 3]);
+        assert!(shape.validate_strides(&shape.strides()).is_ok());
+        assert!(shape.validate_strides(&[3, 0, 1]).is_ok());
+        let err = shape.validate_strides(&[0, 3, 1]).unwrap_err();
+        assert!(err.contains("Axis 0"));
+        assert!(shape.validate_strides(&[3, 1]).is_err());
+    }
+
+    #[test]
+    fn normalize_display() {
+        assert_eq!(Shape::from([1, 1, 3]).normalize_display(), [3]);
+        assert_eq!(Shape::from([1, 3, 1]).normalize_display(), [3, 1]);
+        assert_eq!(Shape::from([1]).normalize_display(), [1]);
+        assert_eq!(Shape::from([1, 1]).normalize_display(), [1]);
+        assert_eq!(Shape::SCALAR.normalize_display(), Shape::SCALAR);
+    }
+
+    #[test]
+    fn product_overflows() {
+        assert!(!Shape::from([1 << 16, 1 << 16]).product_overflows());
+        assert!(Shape::from([usize::MAX, 2]).product_overflows());
+        assert!(!Shape::from([usize::MAX, 2, 0]).product_overflows());
+        assert!(!Shape::SCALAR.product_overflows());
+    }
+
+    #[test]
+    fn grid_for_width() {
+        assert_eq!(Shape::from(5).grid_for_width(10), [1, 5]);
+        assert_eq!(Shape::from(25).grid_for_width(10), [3, 10]);
+        assert_eq!(Shape::from(20).grid_for_width(10), [2, 10]);
+        assert_eq!(Shape::from(3).grid_for_width(0), [3, 1]);
+        assert_eq!(Shape::EMPTY_LIST.grid_for_width(10), [0, 0]);
+    }
+
+    #[test]
+    fn axis_stride() {
+        let shape = Shape::from([2, 3, 4]);
+        for (axis, &stride) in shape.strides().iter().enumerate() {
+            assert_eq!(shape.axis_stride(axis), Some(stride));
+        }
+        assert_eq!(shape.axis_stride(2), Some(1));
+        assert_eq!(shape.axis_stride(3), None);
+    }
+
+    #[test]
+    fn more_specific() {
+        let cases: [(Shape, Shape, Option<Shape>); 4] = [
+            ([1, 3].into(), [2, 1].into(), Some([2, 3].into())),
+            ([4].into(), [2, 1, 4].into(), Some([2, 1, 4].into())),
+            (Shape::SCALAR, [2, 3].into(), Some([2, 3].into())),
+            ([2, 3].into(), [4].into(), None),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(a.broadcast_with(&b), expected);
+            assert_eq!(a.more_specific(&b), expected);
+            assert_eq!(b.more_specific(&a), expected);
+        }
+    }
+
+    #[test]
+    fn cells_along() {
+        let shape = Shape::from([2, 3, 4]);
+        let cells: Vec<_> = shape.cells_along(0).unwrap().collect();
+        assert_eq!(cells, [(0, [3, 4].into()), (1, [3, 4].into())]);
+        let cells: Vec<_> = shape.cells_along(1).unwrap().collect();
+        assert_eq!(cells.len(), 3);
+        assert!(
+            cells
+                .iter()
+                .enumerate()
+                .all(|(i, (j, s))| i == *j && s == [2, 4])
+        );
+        assert!(shape.cells_along(3).is_err());
+    }
+
+    #[test]
+    fn parity_counts() {
+        assert_eq!(Shape::from([2, 2]).parity_counts(), (2, 2));
+        assert_eq!(Shape::from([3, 3]).parity_counts(), (5, 4));
+        assert_eq!(Shape::from([0, 3]).parity_counts(), (0, 0));
+        assert_eq!(Shape::SCALAR.parity_counts(), (1, 0));
+        let shape = Shape::from([3, 5, 2]);
+        let mut index = Vec::new();
+        let even = (0..shape.elements())
+            .filter(|&i| {
+                shape.flat_to_dims(i, &mut index);
+                index.iter().sum::<usize>() % 2 == 0
+            })
+            .count();
+        assert_eq!(shape.parity_counts(), (even, shape.elements() - even));
+    }
+
+    #[test]
+    fn transpose_then_reshape() {
+        let shape = Shape::from([2, 3, 4]);
+        assert_eq!(shape.permute_axes(&[2, 0, 1]).unwrap(), [4, 2, 3]);
+        assert!(shape.permute_axes(&[0, 0, 1]).is_err());
+        assert!(shape.permute_axes(&[0, 1]).is_err());
+        assert!(shape.permute_axes(&[0, 1, 3]).is_err());
+        let target = Shape::from([4, 6]);
+        assert_eq!(
+            shape.transpose_then_reshape(&[2, 0, 1], &target),
+            Ok(target)
+        );
+        assert!(
+            shape
+                .transpose_then_reshape(&[2, 0, 1], &[5, 5].into())
+                .is_err()
+        );
+        assert!(
+            shape
+                .transpose_then_reshape(&[2, 2, 1], &[4, 6].into())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn largest_smallest_axis() {
+        let shape = Shape::from([3, 5, 2, 5, 2]);
+        assert_eq!(shape.largest_axis(), Some(1));
+        assert_eq!(shape.smallest_axis(), Some(2));
+        assert_eq!(Shape::from(4).largest_axis(), Some(0));
+        assert_eq!(Shape::SCALAR.largest_axis(), None);
+        assert_eq!(Shape::SCALAR.smallest_axis(), None);
+    }
+
+    #[test]
+    fn drain_collect() {
+        let original = Shape::from([2, 3, 4, 5]);
+        let mut shape = original.clone();
+        let drained = shape.drain_collect(1..3);
+        assert_eq!(drained, [3, 4]);
+        assert_eq!(shape, [2, 5]);
+        for (i, &dim) in drained.iter().enumerate() {
+            shape.insert(1 + i, dim);
+        }
+        assert_eq!(shape, original);
+    }
+
+    #[test]
+    fn move_axis() {
+        let mut shape = Shape::from([2, 3, 4]);
+        shape.move_axis(0, 2).unwrap();
+        assert_eq!(shape, [3, 4, 2]);
+        shape.move_axis(2, 0).unwrap();
+        assert_eq!(shape, [2, 3, 4]);
+        shape.move_axis(1, 1).unwrap();
+        assert_eq!(shape, [2, 3, 4]);
+        assert!(shape.move_axis(3, 0).is_err());
+        assert!(shape.move_axis(0, 3).is_err());
+        assert_eq!(shape, [2, 3, 4]);
+    }
+
+    #[test]
+    fn all_axes_equal() {
+        assert!(Shape::from([3, 3, 3]).all_axes_equal());
+        assert!(!Shape::from([3, 3, 4]).all_axes_equal());
+        assert!(Shape::from(7).all_axes_equal());
+        assert!(Shape::SCALAR.all_axes_equal());
+    }
+
+    #[test]
+    fn into_dims_vec() {
+        let inline = Shape::from([2, 3]);
+        assert!(!inline.dims.spilled());
+        assert_eq!(inline.into_dims_vec(), [2, 3]);
+        let heap = Shape::from([2, 3, 4, 5]);
+        assert!(heap.dims.spilled());
+        let ptr = heap.as_ptr();
+        let vec = heap.into_dims_vec();
+        assert_eq!(vec, [2, 3, 4, 5]);
+        assert_eq!(vec.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn from_row_lengths() {
+        assert_eq!(Shape::from_row_lengths(&[3, 3, 3]), [3, 3]);
+        assert_eq!(Shape::from_row_lengths(&[1, 4, 2]), [3, 4]);
+        assert_eq!(Shape::from_row_lengths(&[0, 0]), [2, 0]);
+        assert_eq!(Shape::from_row_lengths(&[]), [0, 0]);
+    }
+
+    #[test]
+    fn elements_plus() {
+        let shape = Shape::from([2, 3]);
+        assert_eq!(shape.elements_plus(0), Some(6));
+        assert_eq!(shape.elements_plus(1), Some(9));
+        assert_eq!(Shape::SCALAR.elements_plus(2), Some(3));
+        assert_eq!(Shape::from([usize::MAX, 1]).elements_plus(1), None);
+        assert_eq!(Shape::from([2, usize::MAX]).elements_plus(1), None);
+    }
+
+    #[test]
+    fn axes_rev_enumerated() {
+        let shape = Shape::from([2, 3, 4]);
+        let axes: Vec<_> = shape.axes_rev_enumerated().collect();
+        assert_eq!(axes, [(2, 4), (1, 3), (0, 2)]);
+        assert_eq!(Shape::SCALAR.axes_rev_enumerated().count(), 0);
+    }
+
+    #[test]
+    fn assert_eq_shape() {
+        let shape = Shape::from([2, 3, 4]);
+        assert!(shape.assert_eq_shape(&[2, 3, 4].into()).is_ok());
+        let err = shape.assert_eq_shape(&[2, 5, 4].into()).unwrap_err();
+        assert!(err.contains("axis 1: expected 5, found 3"));
+        assert!(!err.contains("rank"));
+        let err = shape.assert_eq_shape(&[2, 3].into()).unwrap_err();
+        assert!(err.contains("rank: expected 2, found 3"));
+        assert!(!err.contains("axis"));
+    }
+
+    #[test]
+    fn with_prepended() {
+        assert_eq!(Shape::SCALAR.with_prepended(3), [3]);
+        let shape = Shape::from([2, 3]);
+        let prepended = shape.with_prepended(4);
+        assert_eq!(prepended, [4, 2, 3]);
+        assert_eq!(prepended.elements(), 4 * shape.elements());
+    }
+
+    #[test]
+    fn loop_bounds() {
+        let shape = Shape::from([2, 3, 4]);
+        let bounds = shape.loop_bounds();
+        assert_eq!(bounds, [(2, 12), (3, 4), (4, 1)]);
+        let [(n0, s0), (n1, s1), (n2, s2)] = bounds[..] else {
+            unreachable!()
+        };
+        let mut offsets = Vec::new();
+        for i in 0..n0 {
+            for j in 0..n1 {
+                for k in 0..n2 {
+                    let offset = i * s0 + j * s1 + k * s2;
+                    assert_eq!(shape.dims_to_flat([i, j, k]), Some(offset));
+                    offsets.push(offset);
+                }
+            }
+        }
+        assert_eq!(offsets, (0..shape.elements()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn flatten_after() {
+        let mut shape = Shape::from([2, 3, 4, 5]);
+        shape.flatten_after(2);
+        assert_eq!(shape, [2, 3, 20]);
+        let mut shape = Shape::from([2, 3, 4, 5]);
+        shape.flatten_after(0);
+        assert_eq!(shape, [120]);
+        let mut shape = Shape::from([2, 3, 4, 5]);
+        shape.flatten_after(4);
+        assert_eq!(shape, [2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn strided_shape() {
+        assert!(StridedShape::new([2, 3].into(), vec![1]).is_err());
+        let contiguous = StridedShape::new([2, 3].into(), vec![3, 1]).unwrap();
+        // A transposed view of a [3 × 2] buffer
+        let transposed = StridedShape::new([2, 3].into(), vec![1, 2]).unwrap();
+        assert_eq!(contiguous.offset(&[1, 0]), Some(3));
+        assert_eq!(transposed.offset(&[1, 0]), Some(1));
+        assert_eq!(contiguous.offset(&[0, 2]), Some(2));
+        assert_eq!(transposed.offset(&[0, 2]), Some(4));
+        assert_eq!(transposed.offset(&[2, 0]), None);
+        assert_eq!(transposed.offset(&[0]), None);
+    }
+
+    #[test]
+    fn contiguous_strided_shape() {
+        let shape = Shape::from([2, 3, 4]);
+        let view = StridedShape::contiguous(shape.clone());
+        assert_eq!(view.strides(), [12, 4, 1]);
+        let mut index = Vec::new();
+        for i in 0..shape.elements() {
+            shape.flat_to_dims(i, &mut index);
+            assert_eq!(view.offset(&index), shape.dims_to_flat(&index));
+        }
+    }
+
+    #[test]
+    fn strided_transpose() {
+        let shape = Shape::from([2, 3, 4]);
+        let mut view = StridedShape::contiguous(shape.clone());
+        view.transpose();
+        assert_eq!(view.shape(), &[4, 3, 2]);
+        for i in 0..2 {
+            for j in 0..3 {
+                for k in 0..4 {
+                    assert_eq!(view.offset(&[k, j, i]), shape.dims_to_flat([i, j, k]));
+                }
             }
-            write!(f, "{dim}")?;
         }
-        write!(f, "]")
     }
-}
 
-impl fmt::Display for Shape {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{self:?}")
+    #[test]
+    fn strided_broadcast_to() {
+        let view = StridedShape::contiguous([3, 1].into());
+        let broadcast = view.broadcast_to(&[2, 3, 4].into()).unwrap();
+        assert_eq!(broadcast.strides(), [0, 1, 0]);
+        for j in 0..3 {
+            for i in 0..2 {
+                for k in 0..4 {
+                    assert_eq!(broadcast.offset(&[i, j, k]), Some(j));
+                }
+            }
+        }
+        assert!(view.broadcast_to(&[2, 4].into()).is_err());
+        assert!(view.broadcast_to(&[3].into()).is_err());
     }
-}
 
-impl From<usize> for Shape {
-    fn from(dim: usize) -> Self {
-        Self::from([dim])
+    #[test]
+    fn strided_is_contiguous() {
+        let mut view = StridedShape::contiguous([2, 3].into());
+        assert!(view.is_contiguous());
+        assert!(
+            !view
+                .broadcast_to(&[4, 2, 3].into())
+                .unwrap()
+                .is_contiguous()
+        );
+        view.transpose();
+        assert!(!view.is_contiguous());
     }
-}
 
-impl From<&[usize]> for Shape {
-    fn from(dims: &[usize]) -> Self {
-        Self {
-            dims: dims.iter().copied().collect(),
+    #[test]
+    fn strided_offsets() {
+        let data = [0, 1, 2, 3, 4, 5];
+        let mut view = StridedShape::contiguous([2, 3].into());
+        assert_eq!(view.offsets().collect::<Vec<_>>(), data);
+        view.transpose();
+        let gathered: Vec<_> = view.offsets().map(|i| data[i]).collect();
+        let mut transposed = Vec::new();
+        for j in 0..3 {
+            for i in 0..2 {
+                transposed.push(data[i * 3 + j]);
+            }
         }
+        assert_eq!(gathered, transposed);
+        let scalar = StridedShape::contiguous(Shape::SCALAR);
+        assert_eq!(scalar.offsets().collect::<Vec<_>>(), [0]);
     }
-}
 
-impl From<Vec<usize>> for Shape {
-    fn from(dims: Vec<usize>) -> Self {
-        Self {
-            dims: SmallVec::from_vec(dims),
+    #[test]
+    fn reshape_with_order() {
+        let shape = Shape::from([2, 3, 4]);
+        for order in [MajorOrder::Row, MajorOrder::Column] {
+            let target = Shape::from([6, 4]);
+            assert_eq!(shape.reshape_with_order(&target, order), Ok(target));
+            assert!(shape.reshape_with_order(&[5, 4].into(), order).is_err());
         }
+        let err = (shape.reshape_with_order(&[5].into(), MajorOrder::Column)).unwrap_err();
+        assert!(err.starts_with("Cannot column-major reshape"));
     }
-}
 
-impl<const N: usize> From<[usize; N]> for Shape {
-    fn from(dims: [usize; N]) -> Self {
-        dims.as_slice().into()
+    #[test]
+    fn pad_to_rank_with() {
+        let mut shape = Shape::from(3);
+        shape.pad_to_rank_with(3, 1).unwrap();
+        assert_eq!(shape, [1, 1, 3]);
+        let mut shape = Shape::from(3);
+        shape.pad_to_rank_with(3, 2).unwrap();
+        assert_eq!(shape, [2, 2, 3]);
+        shape.pad_to_rank_with(2, 5).unwrap();
+        assert_eq!(shape, [2, 2, 3]);
+        let mut shape = Shape::from(usize::MAX / 2 + 1);
+        assert!(shape.pad_to_rank_with(2, 2).is_err());
+        assert_eq!(shape, [usize::MAX / 2 + 1]);
     }
-}
 
-impl Deref for Shape {
-    type Target = [usize];
-    fn deref(&self) -> &Self::Target {
-        &self.dims
+    #[test]
+    fn accumulate_elements() {
+        let a = Shape::from([2, 3]);
+        let b = Shape::from([4]);
+        let total = a
+            .accumulate_elements(1)
+            .and_then(|n| b.accumulate_elements(n));
+        assert_eq!(total, Some(24));
+        let big = Shape::from([usize::MAX / 2]);
+        assert_eq!(big.accumulate_elements(3), None);
+        assert_eq!(Shape::from([usize::MAX, 0]).accumulate_elements(3), Some(0));
     }
-}
 
-impl DerefMut for Shape {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.dims
+    #[test]
+    fn list_and_vectors() {
+        let list = Shape::from(3);
+        let row = Shape::from([1, 3]);
+        let col = Shape::from([3, 1]);
+        assert!(list.is_list() && !list.is_row_vector() && !list.is_column_vector());
+        assert!(!row.is_list() && row.is_row_vector() && !row.is_column_vector());
+        assert!(!col.is_list() && !col.is_row_vector() && col.is_column_vector());
+        assert!(!Shape::from([1, 3, 1]).is_row_vector());
     }
-}
 
-impl IntoIterator for Shape {
-    type Item = usize;
-    type IntoIter = <SmallVec<[usize; INLINE_DIMS]> as IntoIterator>::IntoIter;
-    fn into_iter(self) -> Self::IntoIter {
-        self.dims.into_iter()
+    #[test]
+    fn expand_axis() {
+        let mut shape = Shape::from([2, 1, 4]);
+        shape.expand_axis(1, 3).unwrap();
+        assert_eq!(shape, [2, 3, 4]);
+        assert!(shape.expand_axis(1, 5).is_err());
+        assert!(shape.expand_axis(3, 5).is_err());
+        assert_eq!(shape, [2, 3, 4]);
     }
-}
 
-impl<'a> IntoIterator for &'a Shape {
-    type Item = &'a usize;
-    type IntoIter = <&'a [usize] as IntoIterator>::IntoIter;
-    fn into_iter(self) -> Self::IntoIter {
-        self.dims.iter()
+    #[test]
+    fn split_large_axes() {
+        let shape = Shape::from([3, 8, 10]);
+        assert_eq!(shape.split_large_axes(4), [3, 2, 4, 3, 4]);
+        assert_eq!(shape.split_large_axes(10), [3, 8, 10]);
+        assert_eq!(shape.split_large_axes(0), shape);
     }
-}
 
-impl FromIterator<usize> for Shape {
-    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
-        Self {
-            dims: iter.into_iter().collect(),
+    #[test]
+    fn broadcast_all() {
+        let shapes = [[2, 1, 4].into(), [3, 1].into(), [4].into()];
+        assert_eq!(Shape::broadcast_all(&shapes).unwrap(), [2, 3, 4]);
+        assert_eq!(Shape::broadcast_all(&[]).unwrap(), Shape::SCALAR);
+        let shapes = [[1, 1].into(), [3, 1].into(), [1, 5].into(), [4, 1].into()];
+        let err = Shape::broadcast_all(&shapes).unwrap_err();
+        assert_eq!(
+            err,
+            "Cannot broadcast shape 1 [3 × 1] with shape 3 [4 × 1]: \
+            axis -2 has lengths 3 and 4"
+        );
+        let err = Shape::broadcast_all(&[[3, 2].into(), [2].into(), [4].into()]).unwrap_err();
+        assert!(err.contains("shape 0") && err.contains("shape 2") && err.contains("axis -1"));
+    }
+
+    #[test]
+    fn axis_is_unit() {
+        let shape = Shape::from([1, 3]);
+        assert_eq!(shape.axis_is_unit(0), Some(true));
+        assert_eq!(shape.axis_is_unit(1), Some(false));
+        assert_eq!(shape.axis_is_unit(2), None);
+    }
+
+    #[test]
+    fn swap_first_two() {
+        let mut shape = Shape::from([2, 3]);
+        shape.swap_first_two().unwrap();
+        assert_eq!(shape, [3, 2]);
+        let mut shape = Shape::from([2, 3, 4]);
+        shape.swap_first_two().unwrap();
+        assert_eq!(shape, [3, 2, 4]);
+        assert!(Shape::from(2).swap_first_two().is_err());
+    }
+
+    #[test]
+    fn flat_to_dims_n() {
+        let shape = Shape::from([2, 3, 4]);
+        assert_eq!(shape.flat_to_dims_n::<3>(0), Some([0, 0, 0]));
+        assert_eq!(shape.flat_to_dims_n::<3>(17), Some([1, 1, 1]));
+        assert_eq!(shape.flat_to_dims_n::<3>(23), Some([1, 2, 3]));
+        assert_eq!(shape.flat_to_dims_n::<3>(24), None);
+        assert_eq!(shape.flat_to_dims_n::<2>(0), None);
+        assert_eq!(Shape::SCALAR.flat_to_dims_n::<0>(0), Some([]));
+    }
+
+    #[test]
+    fn dims_to_flat_n() {
+        let shape = Shape::from([2, 3, 4]);
+        for flat in 0..shape.elements() {
+            let index = shape.flat_to_dims_n::<3>(flat).unwrap();
+            assert_eq!(shape.dims_to_flat_n(&index), Some(flat));
         }
+        assert_eq!(shape.dims_to_flat_n(&[1, 3, 0]), None);
+        assert_eq!(shape.dims_to_flat_n(&[1, 2]), None);
     }
-}
 
-impl Extend<usize> for Shape {
-    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
-        self.dims.extend(iter);
+    #[test]
+    fn reshape_in_place() {
+        let mut shape = Shape::from([2, 3, 4]);
+        shape.reshape_in_place(&[6, 4].into()).unwrap();
+        assert_eq!(shape, [6, 4]);
+        assert_eq!(shape.len(), 2);
+        shape.reshape_in_place(&[1, 2, 3, 4].into()).unwrap();
+        assert_eq!(shape, [1, 2, 3, 4]);
+        assert!(shape.reshape_in_place(&[5].into()).is_err());
+        assert_eq!(shape, [1, 2, 3, 4]);
     }
-}
 
-impl PartialEq<usize> for Shape {
-    fn eq(&self, other: &usize) -> bool {
-        self == [*other]
+    #[test]
+    fn par_offsets() {
+        let shape = Shape::from([10, 20, 30]);
+        let par: usize = shape.par_offsets().sum();
+        let seq: usize = (0..shape.elements()).sum();
+        assert_eq!(par, seq);
+        assert_eq!(Shape::EMPTY_LIST.par_offsets().count(), 0);
     }
-}
 
-impl PartialEq<usize> for &Shape {
-    fn eq(&self, other: &usize) -> bool {
-        *self == [*other]
+    #[test]
+    fn par_row_spans() {
+        let shape = Shape::from([7, 3]);
+        for chunk_rows in [0, 1, 2, 3, 7, 10] {
+            let spans: Vec<_> = shape.par_row_spans(chunk_rows).collect();
+            let mut next = 0;
+            for span in spans {
+                assert_eq!(span.start, next);
+                assert!(span.end > span.start);
+                next = span.end;
+            }
+            assert_eq!(next, shape.elements());
+        }
+        assert_eq!(Shape::EMPTY_LIST.par_row_spans(2).count(), 0);
     }
-}
 
-impl<const N: usize> PartialEq<[usize; N]> for Shape {
-    fn eq(&self, other: &[usize; N]) -> bool {
-        self == other.as_slice()
+    #[test]
+    fn transpose_cost() {
+        let small = Shape::from([4, 4]);
+        assert_eq!(small.transpose_cost(), 16);
+        assert!(!small.prefer_lazy_transpose(1000));
+        let large = Shape::from([1000, 1000]);
+        assert!(large.prefer_lazy_transpose(1000));
+        let huge = Shape::from([usize::MAX, 2]);
+        assert_eq!(huge.transpose_cost(), usize::MAX);
+        assert_eq!(Shape::from([usize::MAX, 2, 0]).transpose_cost(), 0);
     }
-}
 
-impl<const N: usize> PartialEq<[usize; N]> for &Shape {
-    fn eq(&self, other: &[usize; N]) -> bool {
-        *self == other.as_slice()
+    #[test]
+    fn parse_spec() {
+        assert_eq!(Shape::parse_spec("auto x 4"), Ok(vec![None, Some(4)]));
+        assert_eq!(Shape::parse_spec("2 x 3"), Ok(vec![Some(2), Some(3)]));
+        assert_eq!(Shape::parse_spec("2x_x3"), Ok(vec![Some(2), None, Some(3)]));
+        assert_eq!(Shape::parse_spec(""), Ok(Vec::new()));
+        let err = Shape::parse_spec("2 x foo").unwrap_err();
+        assert!(err.contains("`foo`"));
+        assert!(Shape::parse_spec("2 x").is_err());
+        assert!(Shape::parse_spec("-1 x 2").is_err());
     }
-}
 
-impl PartialEq<[usize]> for Shape {
-    fn eq(&self, other: &[usize]) -> bool {
-        self.dims.as_slice() == other
+    #[test]
+    fn signed_offset() {
+        let shape = Shape::from([2, 3]);
+        assert_eq!(shape.signed_offset(&[1, 2]), Some(5));
+        assert_eq!(shape.signed_offset_wrapping(&[1, 2]), Some(5));
+        assert_eq!(shape.signed_offset(&[-1, 0]), None);
+        assert_eq!(shape.signed_offset_wrapping(&[-1, 0]), Some(3));
+        assert_eq!(shape.signed_offset(&[0, -1]), None);
+        assert_eq!(shape.signed_offset_wrapping(&[0, -1]), Some(2));
+        assert_eq!(shape.signed_offset_wrapping(&[-2, -3]), Some(0));
+        assert_eq!(shape.signed_offset(&[1]), None);
+        assert_eq!(shape.signed_offset(&[1, 2, 5]), None);
+        assert_eq!(shape.signed_offset(&[1, 2, 0]), None);
+        assert_eq!(Shape::SCALAR.signed_offset(&[]), Some(0));
     }
-}
 
-impl PartialEq<[usize]> for &Shape {
-    fn eq(&self, other: &[usize]) -> bool {
-        *self == other
+    #[test]
+    fn signed_offset_wrapping() {
+        let shape = Shape::from([2, 3]);
+        assert_eq!(shape.signed_offset_wrapping(&[-1, -1]), Some(5));
+        assert_eq!(shape.signed_offset_wrapping(&[2, 3]), Some(0));
+        assert_eq!(shape.signed_offset_wrapping(&[5, 7]), Some(4));
+        assert_eq!(shape.signed_offset_wrapping(&[-3, -4]), Some(5));
+        assert_eq!(shape.signed_offset_wrapping(&[1]), None);
+        assert_eq!(shape.signed_offset_wrapping(&[1, 1, 1]), None);
+        assert_eq!(Shape::from([2, 0]).signed_offset_wrapping(&[0, 0]), None);
+        assert_eq!(Shape::SCALAR.signed_offset_wrapping(&[]), Some(0));
     }
-}
 
-impl PartialEq<&[usize]> for Shape {
-    fn eq(&self, other: &&[usize]) -> bool {
-        self.dims.as_slice() == *other
+    #[test]
+    fn reflect_index() {
+        let shape = Shape::from([2, 3]);
+        assert_eq!(shape.reflect_index(1, &[0, 0]), Some(vec![0, 2]));
+        assert_eq!(shape.reflect_index(1, &[1, 1]), Some(vec![1, 1]));
+        assert_eq!(shape.reflect_index(0, &[0, 2]), Some(vec![1, 2]));
+        assert_eq!(shape.reflect_index(2, &[0, 0]), None);
+        assert_eq!(shape.reflect_index(1, &[0, 3]), None);
+        assert_eq!(shape.reflect_index(1, &[0]), None);
     }
-}
 
-impl PartialEq<Shape> for &[usize] {
-    fn eq(&self, other: &Shape) -> bool {
-        other == self
+    #[test]
+    fn add_dims() {
+        let shape = Shape::from([2, 3]);
+        assert_eq!(shape.add_dims(&[1, 4].into()).unwrap(), [3, 7]);
+        let err = shape.add_dims(&[1, usize::MAX].into()).unwrap_err();
+        assert!(err.contains("axis 1"));
+        assert!(shape.add_dims(&[1].into()).is_err());
     }
-}
 
-impl PartialEq<Shape> for [usize] {
-    fn eq(&self, other: &Shape) -> bool {
-        other == self
+    #[test]
+    fn power_of_two_dims() {
+        let shape = Shape::from([4, 8, 1]);
+        assert!(shape.all_dims_power_of_two());
+        assert_eq!(shape.next_power_of_two_shape(), Some(shape));
+        let shape = Shape::from([3, 5, 8]);
+        assert!(!shape.all_dims_power_of_two());
+        assert_eq!(shape.next_power_of_two_shape(), Some([4, 8, 8].into()));
+        assert_eq!(Shape::from([usize::MAX]).next_power_of_two_shape(), None);
+        let half = usize::MAX / 2 + 1;
+        assert_eq!(Shape::from([half, 3]).next_power_of_two_shape(), None);
+    }
+
+    #[test]
+    fn pad_axes_to_pow2() {
+        let (padded, pads) = Shape::from([3, 5]).pad_axes_to_pow2().unwrap();
+        assert_eq!(padded, [4, 8]);
+        assert_eq!(pads, [1, 3]);
+        let (padded, pads) = Shape::from([4, 2]).pad_axes_to_pow2().unwrap();
+        assert_eq!(padded, [4, 2]);
+        assert_eq!(pads, [0, 0]);
+        assert!(Shape::from([usize::MAX]).pad_axes_to_pow2().is_err());
+    }
+
+    #[test]
+    fn axis_coords() {
+        let shape = Shape::from([2, 3]);
+        assert_eq!(shape.axis_coords(1).unwrap().collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!(shape.axis_coords(0).unwrap().count(), 2);
+        assert!(shape.axis_coords(2).is_err());
+    }
+
+    #[test]
+    fn checked_split_off() {
+        let mut shape = Shape::from([2, 3]);
+        assert_eq!(shape.checked_split_off(2), Some(Shape::SCALAR));
+        assert_eq!(shape, [2, 3]);
+        assert_eq!(shape.checked_split_off(3), None);
+        assert_eq!(shape, [2, 3]);
+        assert_eq!(shape.checked_split_off(0), Some([2, 3].into()));
+        assert_eq!(shape, Shape::SCALAR);
+    }
+
+    #[test]
+    fn dims_to_flat_unchecked() {
+        let shape = Shape::from([2, 3, 4]);
+        let unchecked = |index: &[usize]| {
+            assert!(shape.dims_to_flat(index).is_some());
+            // Safety: The index is in bounds
+            unsafe { shape.dims_to_flat_unchecked(index) }
+        };
+        let mut index = Vec::new();
+        for flat in 0..shape.elements() {
+            shape.flat_to_dims(flat, &mut index);
+            assert_eq!(unchecked(&index), flat);
+        }
+    }
+
+    #[test]
+    fn info() {
+        let info = Shape::from([2, 3, 4]).info();
+        assert_eq!(
+            info,
+            ShapeInfo {
+                rank: 3,
+                dims: vec![2, 3, 4],
+                elements: 24,
+                is_empty: false,
+                is_scalar: false,
+            }
+        );
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(json["elements"], 24);
+        let info = Shape::from([usize::MAX, usize::MAX]).info();
+        assert_eq!(info.elements, usize::MAX as u128 * usize::MAX as u128);
+        assert!(Shape::EMPTY_LIST.info().is_empty);
+        assert!(Shape::SCALAR.info().is_scalar);
+    }
+
+    #[test]
+    fn resize_keeping_aspect() {
+        assert_eq!(
+            Shape::from([2, 4]).resize_keeping_aspect(32),
+            Some([4, 8].into())
+        );
+        assert_eq!(
+            Shape::from([4, 8]).resize_keeping_aspect(8),
+            Some([2, 4].into())
+        );
+        assert_eq!(
+            Shape::from([3, 5]).resize_keeping_aspect(60),
+            Some([6, 10].into())
+        );
+        assert_eq!(
+            Shape::from([100, 1]).resize_keeping_aspect(1),
+            Some([1, 1].into())
+        );
+        assert_eq!(Shape::from([2, 4]).resize_keeping_aspect(0), None);
+        assert_eq!(Shape::from([0, 4]).resize_keeping_aspect(8), None);
+        assert_eq!(Shape::from([2, 2, 2]).resize_keeping_aspect(8), None);
+    }
+
+    #[test]
+    fn display_radix() {
+        let shape = Shape::from([255, 16]);
+        assert_eq!(shape.display_radix(16).unwrap(), "[ff × 10]");
+        assert_eq!(shape.display_radix(2).unwrap(), "[11111111 × 10000]");
+        assert_eq!(shape.display_radix(10).unwrap(), shape.to_string());
+        assert_eq!(Shape::from([0]).display_radix(36).unwrap(), "[0]");
+        assert_eq!(Shape::SCALAR.display_radix(8).unwrap(), "[]");
+        assert!(shape.display_radix(1).is_err());
+        assert!(shape.display_radix(37).is_err());
+    }
+
+    #[test]
+    fn cells_for() {
+        let shape = Shape::from([2, 3, 4]);
+        assert_eq!(shape.cells_for(ShapeOp::Transpose), Some(24));
+        assert_eq!(shape.cells_for(ShapeOp::Reverse), Some(24));
+        assert_eq!(shape.cells_for(ShapeOp::Reshape([5, 5].into())), Some(25));
+        assert_eq!(shape.cells_for(ShapeOp::Reduce(1)), Some(8));
+        assert_eq!(shape.cells_for(ShapeOp::Reduce(3)), None);
+        let huge = Shape::from([usize::MAX, 2]);
+        assert_eq!(huge.cells_for(ShapeOp::Transpose), None);
+        assert_eq!(huge.cells_for(ShapeOp::Reduce(1)), Some(usize::MAX));
+    }
+
+    #[test]
+    fn concat_conflict() {
+        let shape = Shape::from([2, 3, 4]);
+        assert_eq!(shape.concat_conflict(&[5, 3, 4].into(), 0), None);
+        assert_eq!(shape.concat_conflict(&[5, 3, 6].into(), 0), Some((2, 4, 6)));
+        assert_eq!(shape.concat_conflict(&[2, 5, 6].into(), 1), Some((2, 4, 6)));
+        assert_eq!(shape.concat_conflict(&[2, 5, 6].into(), 2), Some((1, 3, 5)));
+    }
+
+    #[test]
+    fn total_cells_of() {
+        let shapes = [[2, 3].into(), [4].into(), Shape::SCALAR, Shape::EMPTY_LIST];
+        assert_eq!(Shape::total_cells_of(&shapes), Some(11));
+        assert_eq!(Shape::total_cells_of(&[]), Some(0));
+        let big = Shape::from(usize::MAX);
+        assert_eq!(Shape::total_cells_of(&[big.clone(), 1.into()]), None);
+        assert_eq!(Shape::total_cells_of(&[[usize::MAX, 2].into()]), None);
+    }
+
+    #[test]
+    fn broadcast_compatible() {
+        let a = Shape::from([2, 1]);
+        let b = Shape::from([3]);
+        assert!(a.broadcast_compatible(&b) && b.broadcast_compatible(&a));
+        assert!(!a.is_broadcastable_to(&b));
+        assert!(!b.is_broadcastable_to(&a));
+        assert!(b.is_broadcastable_to(&[2, 3].into()));
+        let c = Shape::from([2, 4]);
+        assert!(!a.broadcast_compatible(&[3, 3].into()));
+        assert!(a.is_broadcastable_to(&c));
+        for (x, y) in [(&a, &b), (&a, &c), (&b, &c)] {
+            assert_eq!(x.broadcast_compatible(y), x.broadcast_with(y).is_some());
+        }
+    }
+
+    #[test]
+    fn trim_unit_axes_to_rank() {
+        let shape = Shape::from([1, 3, 1, 4]);
+        assert_eq!(shape.trim_unit_axes_to_rank(2), [3, 4]);
+        assert_eq!(shape.trim_unit_axes_to_rank(3), [3, 1, 4]);
+        assert_eq!(shape.trim_unit_axes_to_rank(0), [3, 4]);
+        assert_eq!(shape.trim_unit_axes_to_rank(5), shape);
+        assert_eq!(Shape::from([1, 1]).trim_unit_axes_to_rank(1), [1]);
+    }
+
+    #[test]
+    fn deinterleave_last() {
+        let mut shape = Shape::from([4, 6]);
+        shape.deinterleave_last(3).unwrap();
+        assert_eq!(shape, [3, 4, 2]);
+        let mut shape = Shape::from(12);
+        shape.deinterleave_last(3).unwrap();
+        assert_eq!(shape, [3, 4]);
+        let mut shape = Shape::from([4, 5]);
+        assert!(shape.deinterleave_last(3).is_err());
+        assert_eq!(shape, [4, 5]);
+    }
+
+    #[test]
+    fn reshape_preview() {
+        let shape = Shape::from([2, 3, 4]);
+        assert_eq!(
+            shape.reshape_preview(&[6, 4].into()),
+            "reshape [2 × 3 × 4] → [6 × 4] (same 24 elements)"
+        );
+        assert_eq!(
+            shape.reshape_preview(&[5, 4].into()),
+            "reshape [2 × 3 × 4] → [5 × 4] (element count changes 24 → 20, elements truncated)"
+        );
+        assert_eq!(
+            shape.reshape_preview(&[5, 5].into()),
+            "reshape [2 × 3 × 4] → [5 × 5] (element count changes 24 → 25, fill needed)"
+        );
+    }
+
+    #[test]
+    fn guess_labels() {
+        assert_eq!(Shape::from(5).guess_labels(), ["index"]);
+        assert_eq!(Shape::from([2, 3]).guess_labels(), ["row", "col"]);
+        assert!(Shape::from([2, 3, 4]).guess_labels().is_empty());
+        let labels = Shape::from([8, 3, 32, 32]).guess_labels();
+        assert_eq!(labels, ["batch", "channel", "height", "width"]);
+        assert!(Shape::SCALAR.guess_labels().is_empty());
+    }
+
+    #[test]
+    fn strided_row_offsets() {
+        let shape = Shape::from([6, 4]);
+        let offsets = |step| shape.strided_row_offsets(step).collect::<Vec<_>>();
+        assert_eq!(offsets(1), [0, 4, 8, 12, 16, 20]);
+        assert_eq!(offsets(2), [0, 8, 16]);
+        assert_eq!(offsets(10), [0]);
+        assert_eq!(offsets(0), offsets(1));
+    }
+
+    #[test]
+    fn try_scale_row_count() {
+        let mut shape = Shape::default();
+        shape.try_scale_row_count(3).unwrap();
+        assert_eq!(shape, [3]);
+        shape.try_scale_row_count(2).unwrap();
+        assert_eq!(shape, [6]);
+        let mut shape = Shape::from([2, 3]);
+        shape.try_scale_row_count(0).unwrap();
+        assert_eq!(shape, [0, 3]);
+        let mut shape = Shape::from([usize::MAX / 2, 1]);
+        assert!(shape.try_scale_row_count(3).is_err());
+        let mut shape = Shape::from([2, usize::MAX / 2]);
+        assert!(shape.try_scale_row_count(3).is_err());
+        assert_eq!(shape, [2, usize::MAX / 2]);
+    }
+
+    #[test]
+    fn same_dims_multiset() {
+        let shape = Shape::from([2, 3, 4]);
+        assert!(shape.same_dims_multiset(&[4, 2, 3].into()));
+        assert!(shape.same_dims_multiset(&shape));
+        assert!(!shape.same_dims_multiset(&[2, 3, 3].into()));
+        assert!(!shape.same_dims_multiset(&[6, 4].into()));
+    }
+
+    #[test]
+    fn reshape_report() {
+        let shape = Shape::from([2, 3]);
+        let report = shape.reshape_report(&[3, 2].into());
+        assert_eq!(report.mode, ReshapeMode::Exact);
+        assert_eq!(report.delta, 0);
+        let report = shape.reshape_report(&[4, 2].into());
+        assert_eq!((report.source_elements, report.target_elements), (6, 8));
+        assert_eq!(report.mode, ReshapeMode::Fill);
+        assert_eq!(report.delta, 2);
+        let report = shape.reshape_report(&[5].into());
+        assert_eq!(report.mode, ReshapeMode::Truncate);
+        assert_eq!(report.delta, -1);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["mode"], "Truncate");
+    }
+
+    #[test]
+    fn fix_at() {
+        let mut shape = Shape::from([2, 3]);
+        assert_eq!(shape.fix_at(0), Ok(0));
+        assert_eq!(shape, [1, 2, 3]);
+        let mut shape = Shape::from([2, 3]);
+        assert_eq!(shape.fix_at(1), Ok(1));
+        assert_eq!(shape, [2, 1, 3]);
+        let mut shape = Shape::from([2, 3]);
+        assert_eq!(shape.fix_at(5), Ok(2));
+        assert_eq!(shape, [2, 3, 1]);
+    }
+
+    #[test]
+    fn unfix_at() {
+        let mut shape = Shape::from([2, 1, 3]);
+        shape.unfix_at(1).unwrap();
+        assert_eq!(shape, [2, 3]);
+        assert_eq!(
+            shape.unfix_at(0).unwrap_err(),
+            "Cannot unfix axis 0 with length 2"
+        );
+        assert!(shape.unfix_at(2).is_err());
+        assert_eq!(shape, [2, 3]);
+    }
+
+    #[test]
+    fn cartesian() {
+        let shape = Shape::from([2, 3]);
+        let expected = [[0, 0], [0, 1], [0, 2], [1, 0], [1, 1], [1, 2]];
+        let mut forward = Vec::new();
+        let mut iter = shape.cartesian();
+        while let Some(index) = iter.next() {
+            forward.push(index.to_vec());
+        }
+        assert_eq!(forward, expected);
+        let mut reverse = Vec::new();
+        let mut iter = shape.cartesian().rev();
+        while let Some(index) = iter.next() {
+            reverse.push(index.to_vec());
+        }
+        forward.reverse();
+        assert_eq!(reverse, forward);
+        let mut iter = shape.cartesian();
+        assert_eq!(iter.next(), Some([0, 0].as_slice()));
+        assert_eq!(iter.next_back(), Some([1, 2].as_slice()));
+        let scalar = Shape::SCALAR;
+        let mut iter = scalar.cartesian();
+        assert_eq!(iter.next(), Some([].as_slice()));
+        assert_eq!(iter.next(), None);
+        assert_eq!(Shape::EMPTY_LIST.cartesian().next(), None);
+    }
+
+    #[test]
+    fn union_bbox() {
+        let shapes = [[2, 5].into(), [4, 1].into(), [3, 3].into()];
+        assert_eq!(Shape::union_bbox(&shapes).unwrap(), [4, 5]);
+        assert_eq!(Shape::union_bbox(&[]).unwrap(), Shape::SCALAR);
+        assert!(Shape::union_bbox(&[[2, 5].into(), [4].into()]).is_err());
+    }
+
+    #[test]
+    fn intersection_bbox() {
+        let shapes = [[2, 5].into(), [4, 1].into(), [3, 3].into()];
+        assert_eq!(Shape::intersection_bbox(&shapes).unwrap(), [2, 1]);
+        assert_eq!(
+            Shape::intersection_bbox(&[[0, 7].into(), [3, 2].into()]).unwrap(),
+            [0, 2]
+        );
+        assert!(Shape::intersection_bbox(&[[2].into(), [2, 2].into()]).is_err());
+    }
+
+    #[test]
+    fn reinterpret_last_axis() {
+        let shape = Shape::from([3, 4]);
+        assert_eq!(shape.reinterpret_last_axis(4, 8).unwrap(), [3, 2]);
+        assert_eq!(shape.reinterpret_last_axis(8, 4).unwrap(), [3, 8]);
+        assert_eq!(shape.reinterpret_last_axis(4, 1).unwrap(), [3, 16]);
+        assert!(Shape::from([3, 3]).reinterpret_last_axis(4, 8).is_err());
+        assert!(Shape::SCALAR.reinterpret_last_axis(4, 4).is_err());
+        assert!(shape.reinterpret_last_axis(4, 0).is_err());
+    }
+
+    #[test]
+    fn empty_result() {
+        assert_eq!(
+            Shape::from([2, 0, 3]).empty_result(),
+            Some([2, 0, 3].into())
+        );
+        assert_eq!(Shape::from([0]).empty_result(), Some([0].into()));
+        assert_eq!(Shape::from([2, 3]).empty_result(), None);
+        assert_eq!(Shape::SCALAR.empty_result(), None);
+    }
+
+    #[test]
+    fn safe_reshape() {
+        let err = |a: Shape, b: Shape| a.safe_reshape(&b).unwrap_err().into_owned();
+        assert_eq!(
+            err(Shape::SCALAR, [0].into()),
+            "Cannot reshape a scalar to empty shape [0]"
+        );
+        assert_eq!(
+            err([2, 0].into(), Shape::SCALAR),
+            "Cannot reshape empty shape [2 × 0] to a scalar"
+        );
+        assert_eq!(
+            err([3].into(), [0, 3].into()),
+            "Cannot reshape nonempty shape [3] to empty shape [0 × 3]"
+        );
+        assert_eq!(
+            err([2, 3].into(), [4].into()),
+            "Cannot reshape shape [2 × 3] with 6 elements to shape [4] with 4 elements"
+        );
+        let shape = Shape::from([2, 3]);
+        assert_eq!(shape.safe_reshape(&[3, 2].into()).unwrap(), [3, 2]);
+        assert_eq!(Shape::SCALAR.safe_reshape(&[1, 1].into()).unwrap(), [1, 1]);
+        assert_eq!(Shape::from([0, 4]).safe_reshape(&[0].into()).unwrap(), [0]);
+    }
+
+    #[test]
+    fn apply_inverse() {
+        let original = Shape::from([2, 3]);
+        let transforms = [
+            ShapeTransform::Push(4),
+            ShapeTransform::Transpose(vec![2, 0, 1]),
+            ShapeTransform::Insert(1, 5),
+            ShapeTransform::Remove(0),
+            ShapeTransform::Reshape([6, 10].into()),
+            ShapeTransform::Pop,
+        ];
+        let mut shape = original.clone();
+        let mut undo = Vec::new();
+        for t in &transforms {
+            undo.push(shape.inverse(t).unwrap());
+            shape.apply(t).unwrap();
+        }
+        assert_eq!(shape, [6]);
+        for t in undo.iter().rev() {
+            shape.apply(t).unwrap();
+        }
+        assert_eq!(shape, original);
+
+        assert!(Shape::SCALAR.clone().apply(&ShapeTransform::Pop).is_err());
+        assert_eq!(Shape::SCALAR.inverse(&ShapeTransform::Pop), None);
+        assert!(original.clone().apply(&ShapeTransform::Remove(2)).is_err());
+        assert!(
+            original
+                .clone()
+                .apply(&ShapeTransform::Insert(3, 1))
+                .is_err()
+        );
+        assert_eq!(
+            original.inverse(&ShapeTransform::Transpose(vec![0, 0])),
+            None
+        );
+    }
+
+    #[test]
+    fn split_axes_by_threshold() {
+        let shape = Shape::from([1000, 3, 512, 4]);
+        assert_eq!(shape.split_axes_by_threshold(64), (vec![0, 2], vec![1, 3]));
+        assert_eq!(shape.split_axes_by_threshold(4), (vec![0, 2, 3], vec![1]));
+        assert_eq!(shape.split_axes_by_threshold(0), (vec![0, 1, 2, 3], vec![]));
+        assert_eq!(Shape::SCALAR.split_axes_by_threshold(1), (vec![], vec![]));
+    }
+
+    #[test]
+    fn max_index() {
+        assert_eq!(Shape::from([2, 3]).max_index(), Some(vec![1, 2]));
+        assert_eq!(Shape::from([1, 5, 1]).max_index(), Some(vec![0, 4, 0]));
+        assert_eq!(Shape::SCALAR.max_index(), Some(vec![]));
+        assert_eq!(Shape::from([2, 0]).max_index(), None);
+    }
+
+    #[test]
+    fn ensure_min_rank() {
+        assert_eq!(Shape::SCALAR.ensure_min_rank(3), [1, 1, 1]);
+        assert_eq!(Shape::from([4]).ensure_min_rank(3), [1, 1, 4]);
+        assert_eq!(Shape::from([2, 3, 4, 5]).ensure_min_rank(3), [2, 3, 4, 5]);
+        assert_eq!(Shape::from([2, 3]).ensure_min_rank(0), [2, 3]);
+    }
+
+    #[test]
+    fn ensure_max_rank() {
+        let shape = Shape::from([2, 3, 4, 5]);
+        let demoted = shape.ensure_max_rank(2);
+        assert_eq!(demoted, [24, 5]);
+        assert_eq!(demoted.elements(), shape.elements());
+        assert_eq!(shape.ensure_max_rank(3), [6, 4, 5]);
+        assert_eq!(shape.ensure_max_rank(4), [2, 3, 4, 5]);
+        assert_eq!(shape.ensure_max_rank(0), [120]);
+        assert_eq!(Shape::SCALAR.ensure_max_rank(2), Shape::SCALAR);
+        assert_eq!(Shape::from([7]).ensure_max_rank(1), [7]);
+    }
+
+    #[test]
+    fn select_axes_mask() {
+        let shape = Shape::from([2, 3, 4, 5]);
+        assert_eq!(shape.select_axes_mask(0b0101).unwrap(), [2, 4]);
+        assert_eq!(shape.select_axes_mask(0b1110).unwrap(), [3, 4, 5]);
+        assert_eq!(shape.select_axes_mask(0b1111).unwrap(), shape);
+        assert_eq!(shape.select_axes_mask(0).unwrap(), Shape::SCALAR);
+        assert!(shape.select_axes_mask(0b10000).is_err());
+        assert!(Shape::SCALAR.select_axes_mask(1).is_err());
+        let mut big = Shape::from(vec![1; 65]);
+        big[1] = 2;
+        big[64] = 3;
+        assert_eq!(big.select_axes_mask(0).unwrap(), Shape::SCALAR);
+        assert_eq!(big.select_axes_mask(0b11).unwrap(), [1, 2]);
+        assert_eq!(big.select_axes_mask(u64::MAX).unwrap().len(), 64);
+    }
+
+    #[test]
+    fn complement_axes() {
+        let shape = Shape::from([2, 3, 4, 5]);
+        assert_eq!(shape.complement_axes(&[0, 2]).unwrap(), [1, 3]);
+        assert_eq!(shape.complement_axes(&[3, 1]).unwrap(), [0, 2]);
+        assert_eq!(shape.complement_axes(&[]).unwrap(), [0, 1, 2, 3]);
+        assert!(shape.complement_axes(&[0, 1, 2, 3]).unwrap().is_empty());
+        assert!(shape.complement_axes(&[1, 1]).is_err());
+        assert!(shape.complement_axes(&[4]).is_err());
+    }
+
+    #[test]
+    fn reduce_axes() {
+        let shape = Shape::from([2, 3, 4]);
+        assert_eq!(shape.reduce_axes(&[0, 2], false).unwrap(), [3]);
+        assert_eq!(shape.reduce_axes(&[0, 2], true).unwrap(), [1, 3, 1]);
+        assert_eq!(shape.reduce_axes(&[], false).unwrap(), shape);
+        assert_eq!(shape.reduce_axes(&[2, 1, 0], false).unwrap(), Shape::SCALAR);
+        assert!(shape.reduce_axes(&[0, 0], true).is_err());
+        assert!(shape.reduce_axes(&[3], false).is_err());
+    }
+
+    #[test]
+    fn moveaxis() {
+        let mut shape = Shape::from([2, 3, 4, 5]);
+        shape.moveaxis(&[0], &[3]).unwrap();
+        assert_eq!(shape, [3, 4, 5, 2]);
+        let mut shape = Shape::from([2, 3, 4, 5]);
+        shape.moveaxis(&[0, 1], &[3, 2]).unwrap();
+        assert_eq!(shape, [4, 5, 3, 2]);
+        let mut shape = Shape::from([2, 3, 4, 5]);
+        shape.moveaxis(&[3, 2], &[0, 1]).unwrap();
+        assert_eq!(shape, [5, 4, 2, 3]);
+
+        let mut shape = Shape::from([2, 3, 4]);
+        assert!(shape.moveaxis(&[0, 1], &[2]).is_err());
+        assert!(shape.moveaxis(&[3], &[0]).is_err());
+        assert!(shape.moveaxis(&[0], &[3]).is_err());
+        assert!(shape.moveaxis(&[0, 0], &[1, 2]).is_err());
+        assert!(shape.moveaxis(&[0, 1], &[2, 2]).is_err());
+        assert_eq!(shape, [2, 3, 4]);
+    }
+
+    #[test]
+    fn as_image_shape() {
+        assert_eq!(
+            Shape::from([480, 640, 3]).as_image_shape(),
+            Some((480, 640, 3))
+        );
+        assert_eq!(Shape::from([32, 32, 4]).as_image_shape(), Some((32, 32, 4)));
+        assert_eq!(Shape::from([32, 16, 1]).as_image_shape(), Some((32, 16, 1)));
+        assert_eq!(Shape::from([32, 16]).as_image_shape(), Some((32, 16, 1)));
+        assert_eq!(Shape::from([32, 16, 2]).as_image_shape(), None);
+        assert_eq!(Shape::from([32]).as_image_shape(), None);
+        assert_eq!(Shape::from([2, 32, 32, 3]).as_image_shape(), None);
+    }
+
+    #[test]
+    fn indices_limited() {
+        let shape = Shape::from([2, 2]);
+        let indices: Vec<_> = shape.indices_limited(4).unwrap().collect();
+        assert_eq!(indices, [[0, 0], [0, 1], [1, 0], [1, 1]]);
+        assert_eq!(Shape::SCALAR.indices_limited(1).unwrap().count(), 1);
+        assert_eq!(Shape::from([3, 0]).indices_limited(0).unwrap().count(), 0);
+        assert!(shape.indices_limited(3).is_err());
+        assert!(
+            Shape::from([1 << 40, 1 << 40])
+                .indices_limited(usize::MAX)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn dims_to_flat_batch() {
+        let shape = Shape::from([2, 3]);
+        let mut out = vec![99];
+        shape.dims_to_flat_batch(&[0, 0, 1, 2, 2, 0, 0, 3, 1, 1, 7], 2, &mut out);
+        assert_eq!(out, [99, 0, 5, usize::MAX, usize::MAX, 4]);
+        out.clear();
+        shape.dims_to_flat_batch(&[0, 0, 0], 3, &mut out);
+        assert_eq!(out, [usize::MAX]);
+        out.clear();
+        shape.dims_to_flat_batch(&[1, 2], 0, &mut out);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn reshape_auto_mode() {
+        let shape = Shape::from([2, 3]);
+        for allow_fill in [false, true] {
+            assert_eq!(
+                shape.reshape_auto_mode(&[3, 2].into(), allow_fill).unwrap(),
+                ([3, 2].into(), ReshapeMode::Exact)
+            );
+        }
+        assert_eq!(
+            shape.reshape_auto_mode(&[4, 2].into(), true).unwrap(),
+            ([4, 2].into(), ReshapeMode::Fill)
+        );
+        assert_eq!(
+            shape.reshape_auto_mode(&[4].into(), true).unwrap(),
+            ([4].into(), ReshapeMode::Truncate)
+        );
+        assert!(shape.reshape_auto_mode(&[4, 2].into(), false).is_err());
+        assert!(shape.reshape_auto_mode(&[4].into(), false).is_err());
+    }
+
+    #[test]
+    fn strided_reversed_offsets() {
+        let mut view = StridedShape::contiguous([2, 3].into());
+        let mut reversed: Vec<_> = view.offsets().collect();
+        reversed.reverse();
+        assert_eq!(view.reversed_offsets().collect::<Vec<_>>(), reversed);
+        assert_eq!(reversed, [5, 4, 3, 2, 1, 0]);
+
+        view.transpose();
+        let mut reversed: Vec<_> = view.offsets().collect();
+        reversed.reverse();
+        assert_eq!(view.reversed_offsets().collect::<Vec<_>>(), reversed);
+        assert_eq!(reversed, [5, 2, 4, 1, 3, 0]);
+
+        let view = StridedShape::contiguous([3].into())
+            .broadcast_to(&[2, 3].into())
+            .unwrap();
+        assert_eq!(
+            view.reversed_offsets().collect::<Vec<_>>(),
+            [2, 1, 0, 2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn offset_with_strides() {
+        let shape = Shape::from([2, 3, 4]);
+        let strides = shape.strides();
+        for index in [[0, 0, 0], [1, 2, 3], [0, 1, 2]] {
+            assert_eq!(
+                shape.offset_with_strides(&index, &strides),
+                shape.dims_to_flat(index)
+            );
+        }
+        let transposed = Shape::from([4, 3, 2]);
+        let strides = [1, 4, 12];
+        assert_eq!(
+            transposed.offset_with_strides(&[3, 2, 1], &strides),
+            Some(23)
+        );
+        assert_eq!(
+            transposed.offset_with_strides(&[1, 0, 1], &strides),
+            Some(13)
+        );
+        assert_eq!(transposed.offset_with_strides(&[4, 0, 0], &strides), None);
+        assert_eq!(transposed.offset_with_strides(&[0, 0], &strides), None);
+        assert_eq!(transposed.offset_with_strides(&[0, 0, 0], &[1, 4]), None);
+    }
+
+    #[test]
+    fn row_cache_lines() {
+        assert_eq!(Shape::from([10, 16]).row_cache_lines(4, 64), 1);
+        assert_eq!(Shape::from([10, 32]).row_cache_lines(4, 64), 2);
+        assert_eq!(Shape::from([10, 17]).row_cache_lines(4, 64), 2);
+        assert_eq!(Shape::from([10, 3, 5]).row_cache_lines(8, 64), 2);
+        assert_eq!(Shape::from([10, 0]).row_cache_lines(8, 64), 0);
+        assert_eq!(Shape::from([10, 16]).row_cache_lines(4, 0), 0);
+    }
+
+    #[test]
+    fn einsum_labels() {
+        assert_eq!(Shape::SCALAR.einsum_labels('a'), "");
+        assert_eq!(Shape::from([2, 3, 4]).einsum_labels('a'), "abc");
+        assert_eq!(Shape::from([2, 3]).einsum_labels('i'), "ij");
+        assert_eq!(Shape::from([2, 3, 4, 5]).einsum_labels('X'), "XYZA");
+        assert_eq!(Shape::from([2]).einsum_labels('?'), "a");
+        assert_eq!(Shape::from(vec![1; 28]).einsum_labels('a').len(), 28);
+        assert!(Shape::from(vec![1; 28]).einsum_labels('a').ends_with("zab"));
+    }
+
+    #[test]
+    fn batch_feature_split() {
+        assert_eq!(Shape::SCALAR.batch_feature_split(), (1, Shape::SCALAR));
+        assert_eq!(Shape::from([5]).batch_feature_split(), (5, [1].into()));
+        assert_eq!(Shape::from([5, 3]).batch_feature_split(), (5, [3].into()));
+        assert_eq!(
+            Shape::from([8, 3, 32, 32]).batch_feature_split(),
+            (8, [3072].into())
+        );
+    }
+
+    #[test]
+    fn running_max() {
+        assert_eq!(Shape::from([2, 5, 3]).running_max(), [2, 5, 5]);
+        assert_eq!(Shape::from([1, 2, 3]).running_max(), [1, 2, 3]);
+        assert_eq!(Shape::from([4, 2, 1]).running_max(), [4, 4, 4]);
+        assert_eq!(Shape::from([0, 3, 1, 7, 2]).running_max(), [0, 3, 3, 7, 7]);
+        assert!(Shape::SCALAR.running_max().is_empty());
+    }
+
+    #[test]
+    fn strided_reshape() {
+        let view = StridedShape::contiguous([2, 3, 4].into());
+        let reshaped = view.reshape(&[6, 4].into()).unwrap();
+        assert!(reshaped.is_contiguous());
+        assert_eq!(
+            reshaped.offsets().collect::<Vec<_>>(),
+            (0..24).collect::<Vec<_>>()
+        );
+        assert!(view.reshape(&[24, 1].into()).unwrap().is_contiguous());
+        assert!(view.reshape(&[1, 2, 12].into()).unwrap().is_contiguous());
+        assert_eq!(view.reshape(&[5, 5].into()), Err(ReshapeNeedsCopy));
+
+        let mut transposed = view.clone();
+        transposed.transpose();
+        assert_eq!(transposed.shape(), &[4, 3, 2]);
+        assert_eq!(transposed.reshape(&[12, 2].into()), Err(ReshapeNeedsCopy));
+        assert_eq!(transposed.reshape(&[24].into()), Err(ReshapeNeedsCopy));
+        // Splitting or adding unit axes keeps the existing layout
+        let split = transposed.reshape(&[2, 2, 1, 3, 2].into()).unwrap();
+        let expected: Vec<_> = transposed.offsets().collect();
+        assert_eq!(split.offsets().collect::<Vec<_>>(), expected);
+
+        let broadcast = StridedShape::contiguous([4].into())
+            .broadcast_to(&[3, 4].into())
+            .unwrap();
+        assert_eq!(broadcast.reshape(&[12].into()), Err(ReshapeNeedsCopy));
+        let split = broadcast.reshape(&[3, 2, 2].into()).unwrap();
+        let expected: Vec<_> = broadcast.offsets().collect();
+        assert_eq!(split.offsets().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn infer_from_len() {
+        assert_eq!(
+            Shape::infer_from_len(&[Some(2), None, Some(4)], 24).unwrap(),
+            [2, 3, 4]
+        );
+        assert_eq!(Shape::infer_from_len(&[None], 7).unwrap(), [7]);
+        assert_eq!(Shape::infer_from_len(&[None, Some(3)], 0).unwrap(), [0, 3]);
+        assert_eq!(
+            Shape::infer_from_len(&[Some(2), Some(3)], 6).unwrap(),
+            [2, 3]
+        );
+        assert_eq!(Shape::infer_from_len(&[], 1).unwrap(), Shape::SCALAR);
+        assert!(Shape::infer_from_len(&[Some(2), None], 7).is_err());
+        assert!(Shape::infer_from_len(&[None, Some(2), None], 8).is_err());
+        assert!(Shape::infer_from_len(&[Some(2), Some(3)], 5).is_err());
+        assert!(Shape::infer_from_len(&[Some(0), None], 0).is_err());
+    }
+
+    #[test]
+    fn access_signature() {
+        let sig = |dims: &[usize]| Shape::from(dims).access_signature();
+        assert_eq!(sig(&[2, 1, 3]), sig(&[100, 1, 7]));
+        assert_eq!(sig(&[0, 5]), sig(&[4, 4]));
+        assert_ne!(sig(&[2, 1, 3]), sig(&[2, 3, 1]));
+        assert_ne!(sig(&[2, 3]), sig(&[2, 3, 4]));
+        assert_ne!(sig(&[1]), sig(&[2]));
+        assert_ne!(sig(&[]), sig(&[1]));
+    }
+
+    #[test]
+    fn collapse_unit_runs() {
+        assert_eq!(Shape::from([1, 1, 3, 1, 1]).collapse_unit_runs(), [1, 3, 1]);
+        assert_eq!(Shape::from([1, 1, 1, 2, 3]).collapse_unit_runs(), [1, 2, 3]);
+        assert_eq!(Shape::from([2, 1, 1, 1, 3]).collapse_unit_runs(), [2, 1, 3]);
+        assert_eq!(Shape::from([2, 3, 1, 1]).collapse_unit_runs(), [2, 3, 1]);
+        assert_eq!(Shape::from([2, 3]).collapse_unit_runs(), [2, 3]);
+        assert_eq!(Shape::SCALAR.collapse_unit_runs(), Shape::SCALAR);
+        let shape = Shape::from([1, 4, 1, 1, 5, 1]);
+        assert_eq!(shape.collapse_unit_runs().elements(), shape.elements());
+    }
+
+    #[test]
+    fn offset_clamped() {
+        let shape = Shape::from([2, 3, 4]);
+        for index in [[0, 0, 0], [1, 2, 3], [1, 0, 2]] {
+            assert_eq!(
+                Some(shape.offset_clamped(&index)),
+                shape.dims_to_flat(index)
+            );
+        }
+        assert_eq!(
+            shape.offset_clamped(&[5, 1, 9]),
+            shape.dims_to_flat([1, 1, 3]).unwrap()
+        );
+        assert_eq!(shape.offset_clamped(&[9, 9, 9]), 23);
+        assert_eq!(shape.offset_clamped(&[1]), 12);
+        assert_eq!(Shape::from([0, 3]).offset_clamped(&[4, 4]), 0);
+        assert_eq!(Shape::SCALAR.offset_clamped(&[3]), 0);
+    }
+
+    #[test]
+    fn reshape_middle() {
+        let shape = Shape::from([2, 3, 4, 5]);
+        assert_eq!(shape.reshape_middle(&[12]).unwrap(), [2, 12, 5]);
+        assert_eq!(shape.reshape_middle(&[2, 3, 2]).unwrap(), [2, 2, 3, 2, 5]);
+        assert_eq!(Shape::from([2, 5]).reshape_middle(&[]).unwrap(), [2, 5]);
+        assert_eq!(
+            Shape::from([2, 5]).reshape_middle(&[1, 1]).unwrap(),
+            [2, 1, 1, 5]
+        );
+        assert!(shape.reshape_middle(&[11]).is_err());
+        assert!(shape.reshape_middle(&[]).is_err());
+        assert!(Shape::from([6]).reshape_middle(&[]).is_err());
+    }
+
+    #[test]
+    fn buffer_len() {
+        assert_eq!(Shape::from([2, 3, 4]).buffer_len(), Ok(24));
+        assert_eq!(Shape::SCALAR.buffer_len(), Ok(1));
+        assert_eq!(Shape::from([usize::MAX, 0]).buffer_len(), Ok(0));
+        assert!(Shape::from([usize::MAX, 2]).buffer_len().is_err());
+    }
+
+    #[test]
+    fn metadata_only_reshape() {
+        let shape = Shape::from([2, 3, 4]);
+        assert!(shape.metadata_only_reshape(&[6, 4].into()));
+        assert!(shape.metadata_only_reshape(&[24].into()));
+        assert!(Shape::SCALAR.metadata_only_reshape(&[1, 1].into()));
+        assert!(!shape.metadata_only_reshape(&[5, 5].into()));
+        assert!(!Shape::SCALAR.metadata_only_reshape(&[0].into()));
+    }
+
+    #[test]
+    fn scale_all() {
+        let shape = Shape::from([2, 3, 4]);
+        assert_eq!(shape.scale_all(1).unwrap(), shape);
+        assert_eq!(shape.scale_all(2).unwrap(), [4, 6, 8]);
+        assert_eq!(shape.scale_all(0).unwrap(), [0, 0, 0]);
+        assert_eq!(Shape::SCALAR.scale_all(5).unwrap(), Shape::SCALAR);
+        assert!(Shape::from([usize::MAX]).scale_all(2).is_err());
+        assert!(
+            Shape::from([1 << 20, 1 << 20, 1 << 20])
+                .scale_all(4)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn first_axis_where() {
+        let shape = Shape::from([1, 3, 10, 20]);
+        assert_eq!(shape.first_axis_where(|dim| dim > 5), Some(2));
+        assert_eq!(shape.first_axis_where(|dim| dim != 1), Some(1));
+        assert_eq!(shape.first_axis_where(|dim| dim > 20), None);
+        assert_eq!(Shape::SCALAR.first_axis_where(|_| true), None);
+    }
+
+    #[test]
+    fn diff_mask() {
+        let shape = Shape::from([2, 3, 4]);
+        assert_eq!(shape.diff_mask(&[2, 3, 4].into()), Ok(0));
+        assert_eq!(shape.diff_mask(&[5, 3, 4].into()), Ok(0b001));
+        assert_eq!(shape.diff_mask(&[2, 3, 1].into()), Ok(0b100));
+        assert_eq!(shape.diff_mask(&[1, 3, 1].into()), Ok(0b101));
+        assert_eq!(Shape::SCALAR.diff_mask(&Shape::SCALAR), Ok(0));
+        assert!(shape.diff_mask(&[2, 3].into()).is_err());
+        let big = Shape::from(vec![1; 65]);
+        assert!(big.diff_mask(&big).is_err());
+    }
+
+    #[test]
+    fn row_spans_rev() {
+        let shape = Shape::from([3, 2, 2]);
+        let spans: Vec<_> = shape.row_spans().collect();
+        assert_eq!(spans, [(0, 0..4), (1, 4..8), (2, 8..12)]);
+        let mut rev: Vec<_> = shape.row_spans_rev().collect();
+        assert_eq!(rev, [(2, 8..12), (1, 4..8), (0, 0..4)]);
+        rev.reverse();
+        assert_eq!(rev, spans);
+        assert_eq!(rev.first().unwrap().1.start, 0);
+        assert_eq!(rev.last().unwrap().1.end, shape.elements());
+        assert_eq!(
+            Shape::SCALAR.row_spans_rev().collect::<Vec<_>>(),
+            [(0, 0..1)]
+        );
+        assert_eq!(Shape::EMPTY_LIST.row_spans_rev().count(), 0);
+    }
+
+    #[test]
+    fn to_nonzero() {
+        use std::num::NonZeroUsize;
+        let dims = Shape::from([2, 3]).to_nonzero().unwrap();
+        assert_eq!(
+            dims,
+            [NonZeroUsize::new(2).unwrap(), NonZeroUsize::new(3).unwrap()]
+        );
+        assert!(Shape::SCALAR.to_nonzero().unwrap().is_empty());
+        assert_eq!(
+            Shape::from([2, 0, 3]).to_nonzero().unwrap_err(),
+            "Axis 1 of shape [2 × 0 × 3] has length 0"
+        );
+    }
+
+    #[test]
+    fn plan_reshape() {
+        let shape = Shape::from([2, 3, 4]);
+        assert_eq!(
+            shape.plan_reshape(&[6, 4].into()),
+            Some(vec![[6, 4].into()])
+        );
+        assert_eq!(shape.plan_reshape(&shape), Some(vec![shape.clone()]));
+        assert_eq!(shape.plan_reshape(&[5, 5].into()), None);
+    }
+
+    #[test]
+    fn hypercube() {
+        assert_eq!(Shape::hypercube(3, 2), [3, 3]);
+        assert_eq!(Shape::hypercube(2, 4), [2, 2, 2, 2]);
+        assert_eq!(Shape::hypercube(5, 0), Shape::SCALAR);
+        assert_eq!(Shape::hypercube(0, 3), [0, 0, 0]);
+        assert_eq!(Shape::hypercube(4, 3).elements(), 64);
+        assert_eq!(Shape::try_hypercube(1, 100).unwrap().len(), 100);
+        assert!(Shape::try_hypercube(2, 64).is_err());
+        assert!(Shape::try_hypercube(1 << 32, 2).is_err());
+    }
+
+    #[test]
+    fn split_unit_prefix() {
+        assert_eq!(
+            Shape::from([1, 1, 3, 4]).split_unit_prefix(),
+            (2, [3, 4].into())
+        );
+        assert_eq!(
+            Shape::from([1, 3, 1]).split_unit_prefix(),
+            (1, [3, 1].into())
+        );
+        assert_eq!(Shape::from([3, 4]).split_unit_prefix(), (0, [3, 4].into()));
+        assert_eq!(
+            Shape::from([1, 1, 1]).split_unit_prefix(),
+            (3, Shape::SCALAR)
+        );
+        assert_eq!(Shape::SCALAR.split_unit_prefix(), (0, Shape::SCALAR));
+    }
+
+    #[test]
+    fn broadcast_checked() {
+        let shape = Shape::from([2, 3]);
+        assert_eq!(shape.broadcast_checked(&[3].into()).unwrap(), [2, 3]);
+        assert_eq!(
+            shape.broadcast_checked(&[4, 1, 1].into()).unwrap(),
+            [4, 2, 3]
+        );
+        assert_eq!(shape.broadcast_checked(&Shape::SCALAR).unwrap(), [2, 3]);
+        assert_eq!(
+            shape.broadcast_checked(&[4].into()).unwrap_err(),
+            "shapes [2 × 3] and [4] are not broadcastable: axis -1 has lengths 3 and 4"
+        );
+        assert_eq!(
+            shape.broadcast_checked(&[5, 3].into()).unwrap_err(),
+            "shapes [2 × 3] and [5 × 3] are not broadcastable: axis -2 has lengths 2 and 5"
+        );
+        assert_eq!(shape.broadcast_conflict(&[1, 3].into()), None);
+        assert_eq!(shape.broadcast_conflict(&[5, 1].into()), Some((2, 2, 5)));
     }
 }